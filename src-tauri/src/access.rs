@@ -0,0 +1,169 @@
+use std::process::Command;
+
+use anyhow::{anyhow, Context, Result};
+use serde::Serialize;
+
+use crate::dbus::structs::OpenVPN3Config;
+
+const PASSWD: &str = "/etc/passwd";
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct LocalUser {
+    pub uid: u32,
+    /// None when the UID has no account on this machine
+    pub name: Option<String>,
+}
+
+/// Who may use a configuration, see `AccessGrant` in the openvpn3
+/// configuration manager.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigAccess {
+    pub config_path: String,
+    pub owner: Option<LocalUser>,
+    /// The users the configuration was granted to
+    pub users: Vec<LocalUser>,
+    pub public_access: bool,
+    /// Other users can start sessions but not read the content
+    pub locked_down: bool,
+    /// Sealed configurations can no longer be changed
+    pub sealed: bool,
+}
+
+impl ConfigAccess {
+    /// Looks the owner and the users of the configuration up with a single
+    /// query, run off the async runtime.
+    pub async fn from_config(config: &OpenVPN3Config) -> Self {
+        let uids: Vec<u32> = config
+            .owner
+            .iter()
+            .chain(config.acl.iter().flatten())
+            .copied()
+            .collect();
+
+        let keys = uids.iter().map(|uid| uid.to_string()).collect();
+        let found = match blocking_lookup(keys).await {
+            Ok(found) => found,
+            Err(error) => {
+                tracing::warn!("Failed to look up users: {:#}", error);
+                vec![]
+            }
+        };
+        let user = |uid: u32| {
+            found
+                .iter()
+                .find(|user| user.uid == uid)
+                .cloned()
+                .unwrap_or(LocalUser { uid, name: None })
+        };
+
+        Self {
+            config_path: config.path.clone(),
+            owner: config.owner.map(user),
+            users: config.acl.iter().flatten().map(|uid| user(*uid)).collect(),
+            public_access: config.public_access.unwrap_or_default(),
+            locked_down: config.locked_down.unwrap_or_default(),
+            sealed: config.readonly.unwrap_or_default(),
+        }
+    }
+}
+
+/// Resolves a user name, or a numeric UID, to the UID of a local account.
+pub async fn resolve_uid(user: &str) -> Result<u32> {
+    let user = user.trim();
+    if user.is_empty() {
+        return Err(anyhow!("No user given"));
+    }
+
+    let found = blocking_lookup(vec![user.to_string()]).await?;
+    match found.first() {
+        Some(found) => Ok(found.uid),
+        None => user
+            .parse::<u32>()
+            .map_err(|_| anyhow!("There is no user named {}", user)),
+    }
+}
+
+async fn blocking_lookup(keys: Vec<String>) -> Result<Vec<LocalUser>> {
+    if keys.is_empty() {
+        return Ok(vec![]);
+    }
+
+    tokio::task::spawn_blocking(move || lookup(&keys))
+        .await
+        .with_context(|| "User lookup did not finish")?
+}
+
+/// Looks user names or UIDs up with one `getent` call, which also sees
+/// accounts from LDAP or SSSD, and in /etc/passwd when `getent` is not
+/// available. Keys without an account are left out.
+fn lookup(keys: &[String]) -> Result<Vec<LocalUser>> {
+    match Command::new("getent").arg("passwd").args(keys).output() {
+        // getent exits with 2 when a key is not found
+        Ok(output) if output.status.success() || output.status.code() == Some(2) => {
+            return Ok(String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .filter_map(parse_entry)
+                .collect());
+        }
+        Ok(output) => tracing::debug!("getent exited with {}", output.status),
+        Err(error) => tracing::debug!("Failed to run getent: {}", error),
+    }
+
+    let passwd =
+        std::fs::read_to_string(PASSWD).with_context(|| format!("Failed to read {}", PASSWD))?;
+
+    Ok(passwd
+        .lines()
+        .filter_map(parse_entry)
+        .filter(|entry| {
+            keys.iter()
+                .any(|key| entry.name.as_deref() == Some(key) || entry.uid.to_string() == *key)
+        })
+        .collect())
+}
+
+/// Parses a `name:password:uid:gid:...` line of the passwd database.
+fn parse_entry(line: &str) -> Option<LocalUser> {
+    let mut fields = line.split(':');
+    let name = fields.next()?;
+    let uid = fields.nth(1)?.parse().ok()?;
+
+    Some(LocalUser {
+        uid,
+        name: Some(name.to_string()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_passwd_entries() {
+        assert_eq!(
+            parse_entry("alice:x:1000:1000:Alice,,,:/home/alice:/bin/bash"),
+            Some(LocalUser {
+                uid: 1000,
+                name: Some("alice".to_string()),
+            })
+        );
+        assert_eq!(
+            parse_entry("root:x:0:0::/root:/bin/sh").map(|user| user.uid),
+            Some(0)
+        );
+        assert_eq!(parse_entry("broken:x:not-a-uid:0::/:/bin/sh"), None);
+        assert_eq!(parse_entry("short:x"), None);
+        assert_eq!(parse_entry(""), None);
+    }
+
+    #[tokio::test]
+    async fn resolves_names_and_uids() {
+        assert_eq!(resolve_uid("root").await.unwrap(), 0);
+        assert_eq!(resolve_uid(" 0 ").await.unwrap(), 0);
+        // UIDs without an account can still be granted access
+        assert_eq!(resolve_uid("4000000").await.unwrap(), 4000000);
+        assert!(resolve_uid("no-such-user-for-konewka").await.is_err());
+        assert!(resolve_uid("  ").await.is_err());
+    }
+}
@@ -0,0 +1,112 @@
+use anyhow::{anyhow, Result};
+
+use crate::connection::{sets, ConnectOutcome};
+use crate::dbus::openvpn3::OpenVPN3Dbus;
+use crate::settings::SettingsStore;
+
+const USAGE: &str = "Usage:
+  konewka                      open the window
+  konewka sets                 list the connection sets
+  konewka connect <set>        connect a connection set
+  konewka disconnect <set>     disconnect a connection set";
+
+/// What to do without opening the window.
+pub enum Command {
+    ListSets,
+    Connect(String),
+    Disconnect(String),
+}
+
+/// Reads the command from the arguments, none when the window should open.
+/// Logging options are left to `telemetry`, unknown arguments are ignored
+/// as they were before there were commands.
+pub fn parse(args: &[String]) -> Result<Option<Command>> {
+    let mut positional = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-v" | "--verbose" => (),
+            "--log-level" => {
+                args.next();
+            }
+            arg if arg.starts_with("--log-level=") => (),
+            arg => positional.push(arg),
+        }
+    }
+
+    match positional.as_slice() {
+        ["sets"] => Ok(Some(Command::ListSets)),
+        ["connect", name] => Ok(Some(Command::Connect(name.to_string()))),
+        ["disconnect", name] => Ok(Some(Command::Disconnect(name.to_string()))),
+        ["sets" | "connect" | "disconnect", ..] | ["help" | "--help" | "-h", ..] => {
+            Err(anyhow!("{}", USAGE))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Runs the command and returns the exit code.
+pub async fn run(command: Command, openvpn3: &OpenVPN3Dbus, settings: &SettingsStore) -> i32 {
+    let result = match command {
+        Command::ListSets => {
+            for set in settings.get().connection_sets {
+                println!("{}", set.name);
+                for config_path in set.configs {
+                    println!("  {}", config_path);
+                }
+            }
+            Ok(true)
+        }
+        Command::Connect(name) => connect(openvpn3, settings, &name).await,
+        Command::Disconnect(name) => disconnect(openvpn3, settings, &name).await,
+    };
+
+    match result {
+        Ok(true) => 0,
+        Ok(false) => 1,
+        Err(error) => {
+            eprintln!("{:#}", error);
+            1
+        }
+    }
+}
+
+async fn connect(openvpn3: &OpenVPN3Dbus, settings: &SettingsStore, name: &str) -> Result<bool> {
+    let set = sets::find(settings, name)?;
+    let report = sets::connect(openvpn3, settings, &set).await?;
+
+    for member in report.members.iter() {
+        let status = match (&member.outcome, member.already_connected) {
+            (_, true) => "already connected".to_string(),
+            (Some(outcome), _) => format!("{:?}", outcome),
+            (None, _) => "skipped".to_string(),
+        };
+        println!("{}: {}", member.config_path, status);
+    }
+    println!("{}", report.summary);
+
+    // Nothing answers credential or web authentication requests here
+    let waiting: Vec<&str> = report
+        .members
+        .iter()
+        .filter(|member| member.outcome == Some(ConnectOutcome::NeedsInput))
+        .map(|member| member.config_path.as_str())
+        .collect();
+    if !waiting.is_empty() {
+        eprintln!("Waiting for authentication: {}", waiting.join(", "));
+        eprintln!("Finish it in the Konewka window or with `openvpn3 session-auth`");
+        return Ok(false);
+    }
+
+    Ok(report.complete())
+}
+
+async fn disconnect(openvpn3: &OpenVPN3Dbus, settings: &SettingsStore, name: &str) -> Result<bool> {
+    let set = sets::find(settings, name)?;
+
+    for session_path in sets::disconnect(openvpn3, &set).await? {
+        println!("Disconnected {}", session_path);
+    }
+
+    Ok(true)
+}
@@ -1,35 +1,75 @@
-use std::pin::Pin;
+use std::{
+    io::Write,
+    os::unix::fs::{OpenOptionsExt, PermissionsExt},
+    path::Path,
+    pin::Pin,
+};
 
 use futures::Future;
 
 use crate::{
-    dbus::structs::{OpenVPN3Config, OpenVPN3Session},
-    structs::ImportConfigPayload,
-    utils, MyState,
+    access::{self, ConfigAccess},
+    connection::{
+        self,
+        failover::{ActiveGroup, FailoverGroup},
+        fallback::{self, ConnectReport, ProtocolFallback},
+        latency::{self, RemoteLatency},
+        sets::{self, ConnectionSet, SetReport},
+    },
+    dbus::{
+        codes::LOG_VERBOSITY_MAX,
+        networkmanager::{NetworkManagerConnection, NetworkManagerDbus},
+        overrides::{self, ConfigOverride, OverrideDefinition},
+        structs::{Listing, OpenVPN3Config, OpenVPN3Session, SignalScope},
+    },
+    diagnostics,
+    import::{
+        directory::{self, DirectoryImportReport, ImportCandidate},
+        replace,
+        server::{ProfileServer, ProfileTypeAvailability},
+        watch::SyncReport,
+    },
+    logs,
+    ovpn::{self, summary::ConfigSummary, validate::ValidationReport},
+    settings::{ServerSource, WatchedDirectory},
+    structs::{
+        ConfigAutoSelectPayload, ConfigFlagPayload, ConfigLogVerbosityPayload,
+        ConfigOverridePayload, ConfigRemotes, ConfigUserPayload, ConnectRemotePayload,
+        DirectoryImportPayload, ExportConfigPayload, ExportLogsPayload, FailoverGroupStatus,
+        ImportConfigContentPayload, ImportConfigPayload, ImportConfigResult, ImportFailure,
+        ImportNetworkManagerPayload, NetworkManagerImportResult, ProtocolFallbackPayload,
+        RenameConfigPayload, SealConfigPayload, ServerImportPayload, ServerLoginPayload,
+        ServerRefreshPayload, ServerRefreshResult, SessionLogForwardPayload,
+        SessionLogVerbosityPayload,
+    },
+    tray, utils, MyState,
 };
 
 #[tauri::command]
+#[tracing::instrument(skip(state))]
 pub async fn get_openvpn3_configs(
     state: tauri::State<'_, MyState>,
-) -> Result<Vec<OpenVPN3Config>, ()> {
+) -> Result<Listing<OpenVPN3Config>, ()> {
     let openvpn3 = state.openvpn3.clone();
 
     let closure = move || {
         let openvpn3 = openvpn3.clone();
         Box::pin(async move { openvpn3.get_configs().await })
-            as Pin<Box<dyn Future<Output = Result<Vec<OpenVPN3Config>, anyhow::Error>> + Send>>
+            as Pin<Box<dyn Future<Output = Result<Listing<OpenVPN3Config>, anyhow::Error>> + Send>>
     };
 
     let configs = match utils::async_retry(closure, 5).await {
         Ok(configs) => configs,
-        Err(_) => {
-            return Ok(vec![]);
+        Err(error) => {
+            tracing::error!("Failed to fetch configs: {:#}", error);
+            return Ok(Listing::default());
         }
     };
     Ok(configs)
 }
 
 #[tauri::command]
+#[tracing::instrument]
 pub fn select_file() -> Result<String, String> {
     let file = tauri_api::dialog::select(Some(""), Some(""));
     match file {
@@ -41,38 +81,497 @@ pub fn select_file() -> Result<String, String> {
                 return Err("Invalid file selection".to_string());
             }
         },
-        Err(_error) => {
+        Err(error) => {
+            tracing::warn!("Failed to open file dialog: {}", error);
             return Ok("".to_string());
         }
     }
 }
 
 #[tauri::command]
+#[tracing::instrument]
+pub fn validate_config_file(payload: String) -> Result<ValidationReport, String> {
+    match ovpn::prepare::prepare_file(Path::new(&payload)) {
+        Ok(prepared) => Ok(prepared.report),
+        Err(error) => Err(format!("{:#}", error)),
+    }
+}
+
+#[tauri::command]
+#[tracing::instrument(skip(state))]
 pub async fn import_openvpn3_config<'a>(
     payload: ImportConfigPayload,
     state: tauri::State<'a, MyState>,
+) -> Result<ImportConfigResult, String> {
+    let profiles = match ovpn::bundle::load(Path::new(&payload.config_file)) {
+        Ok(profiles) => profiles,
+        Err(error) => return Err(format!("{:#}", error)),
+    };
+
+    let invalid: Vec<String> = profiles
+        .iter()
+        .filter(|profile| !profile.prepared.report.valid)
+        .map(|profile| {
+            format!(
+                "{}:\n{}",
+                profile.source,
+                profile.prepared.report.error_summary()
+            )
+        })
+        .collect();
+
+    if !invalid.is_empty() {
+        let summary = invalid.join("\n");
+        tracing::warn!("Refusing to import invalid config:\n{}", summary);
+        return Err(summary);
+    }
+
+    // A name typed by the user only makes sense for a single profile,
+    // bundles with several profiles keep the names derived from the bundle
+    let single_profile = profiles.len() == 1;
+    let mut result = ImportConfigResult::default();
+
+    for profile in profiles {
+        for inlined in profile.prepared.inlined_files() {
+            tracing::info!(
+                "Inlined {} referenced by {} on line {} of {}",
+                inlined.file,
+                inlined.directive,
+                inlined.line,
+                profile.source
+            );
+        }
+
+        let config_name = if single_profile && !payload.config_name.is_empty() {
+            payload.config_name.clone()
+        } else {
+            profile.name
+        };
+
+        let content = profile.prepared.content;
+        match state
+            .openvpn3
+            .import_config(
+                config_name,
+                content.clone(),
+                payload.single_use,
+                payload.persistent,
+            )
+            .await
+        {
+            Ok(config_path) => {
+                directory::record_content_hash(&state.settings, &config_path, &content);
+                result.config_paths.push(config_path);
+            }
+            Err(error) => {
+                tracing::error!("Failed to import {}: {:#}", profile.source, error);
+                result.failed.push(ImportFailure {
+                    source: profile.source,
+                    error: format!("{:#}", error),
+                });
+            }
+        };
+    }
+
+    if result.config_paths.is_empty() {
+        let failures: Vec<String> = result
+            .failed
+            .iter()
+            .map(|failure| format!("{}: {}", failure.source, failure.error))
+            .collect();
+        return Err(format!("Failed to import config\n{}", failures.join("\n")));
+    }
+
+    Ok(result)
+}
+
+#[tauri::command]
+#[tracing::instrument(skip(payload))]
+pub fn validate_config_content(payload: String) -> ValidationReport {
+    prepare_pasted_content(&payload).report
+}
+
+#[tauri::command]
+#[tracing::instrument(skip(payload, state))]
+pub async fn import_openvpn3_config_content<'a>(
+    payload: ImportConfigContentPayload,
+    state: tauri::State<'a, MyState>,
 ) -> Result<String, String> {
-    println!("Importing config: {:?}", payload);
-    let path = match state.openvpn3.import_config(payload).await {
+    let prepared = prepare_pasted_content(&payload.config_content);
+
+    if !prepared.report.valid {
+        let summary = prepared.report.error_summary();
+        tracing::warn!("Refusing to import invalid config:\n{}", summary);
+        return Err(summary);
+    }
+
+    let config_name = if payload.config_name.is_empty() {
+        ovpn::prepare::suggested_name(&payload.config_content)
+            .unwrap_or_else(|| "Imported config".to_string())
+    } else {
+        payload.config_name
+    };
+
+    match state
+        .openvpn3
+        .import_config(
+            config_name,
+            prepared.content.clone(),
+            payload.single_use,
+            payload.persistent,
+        )
+        .await
+    {
+        Ok(config_path) => {
+            directory::record_content_hash(&state.settings, &config_path, &prepared.content);
+            Ok(config_path)
+        }
+        Err(error) => {
+            tracing::error!("Failed to import config: {:#}", error);
+            Err(format!("Failed to import config: {:#}", error))
+        }
+    }
+}
+
+/// Pasted content has no directory to resolve file references against,
+/// so every referenced file is reported as missing.
+fn prepare_pasted_content(content: &str) -> ovpn::prepare::PreparedConfig {
+    ovpn::prepare::prepare_content(content, |_| {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "pasted configurations must embed referenced files inline",
+        ))
+    })
+}
+
+#[tauri::command]
+#[tracing::instrument(skip(state))]
+pub async fn preview_directory_import<'a>(
+    payload: DirectoryImportPayload,
+    state: tauri::State<'a, MyState>,
+) -> Result<Vec<ImportCandidate>, String> {
+    let base_dir = Path::new(&payload.directory);
+
+    let files = match directory::scan(base_dir, payload.recursive) {
+        Ok(files) => files,
+        Err(error) => return Err(format!("{:#}", error)),
+    };
+
+    match directory::preview(&state.openvpn3, &state.settings, base_dir, &files).await {
+        Ok(candidates) => Ok(candidates),
+        Err(error) => Err(format!("{:#}", error)),
+    }
+}
+
+#[tauri::command]
+#[tracing::instrument(skip(state))]
+pub async fn import_directory<'a>(
+    payload: DirectoryImportPayload,
+    state: tauri::State<'a, MyState>,
+) -> Result<DirectoryImportReport, String> {
+    let base_dir = Path::new(&payload.directory);
+
+    let files = match directory::scan(base_dir, payload.recursive) {
+        Ok(files) => files,
+        Err(error) => return Err(format!("{:#}", error)),
+    };
+
+    let candidates =
+        match directory::preview(&state.openvpn3, &state.settings, base_dir, &files).await {
+            Ok(candidates) => candidates,
+            Err(error) => return Err(format!("{:#}", error)),
+        };
+
+    Ok(directory::import(
+        &state.openvpn3,
+        &state.settings,
+        candidates,
+        payload.single_use,
+        payload.persistent,
+    )
+    .await)
+}
+
+#[tauri::command]
+#[tracing::instrument(skip(payload), fields(url = %payload.url))]
+pub async fn get_server_profile_types(
+    payload: ServerLoginPayload,
+) -> Result<Vec<ProfileTypeAvailability>, String> {
+    match ProfileServer::new(&payload.url, &payload.username, &payload.password) {
+        Ok(server) => Ok(server.available_profile_types().await),
+        Err(error) => Err(format!("{:#}", error)),
+    }
+}
+
+#[tauri::command]
+#[tracing::instrument(skip(payload, state), fields(url = %payload.url))]
+pub async fn import_from_server<'a>(
+    payload: ServerImportPayload,
+    state: tauri::State<'a, MyState>,
+) -> Result<String, String> {
+    let server = match ProfileServer::new(&payload.url, &payload.username, &payload.password) {
+        Ok(server) => server,
+        Err(error) => return Err(format!("{:#}", error)),
+    };
+
+    let content = match server.download(payload.profile_type).await {
+        Ok(content) => content,
+        Err(error) => return Err(format!("{:#}", error)),
+    };
+
+    let prepared = prepare_pasted_content(&content);
+    if !prepared.report.valid {
+        let summary = prepared.report.error_summary();
+        tracing::warn!("Refusing to import invalid config:\n{}", summary);
+        return Err(summary);
+    }
+
+    let config_name = if payload.config_name.is_empty() {
+        server.profile_name(&content)
+    } else {
+        payload.config_name
+    };
+
+    let config_path = match state
+        .openvpn3
+        .import_config(
+            config_name,
+            prepared.content.clone(),
+            payload.single_use,
+            payload.persistent,
+        )
+        .await
+    {
         Ok(config_path) => config_path,
-        Err(_) => {
-            return Ok("Failed to import config".to_string());
+        Err(error) => {
+            tracing::error!("Failed to import config: {:#}", error);
+            return Err(format!("Failed to import config: {:#}", error));
+        }
+    };
+
+    directory::record_content_hash(&state.settings, &config_path, &prepared.content);
+
+    let source = ServerSource {
+        url: server.base_url().to_string(),
+        username: payload.username,
+        profile_type: payload.profile_type,
+        hash: directory::content_hash(content.as_bytes()),
+    };
+    if let Err(error) = state
+        .settings
+        .update_config(&config_path, |config| config.server = Some(source))
+    {
+        tracing::warn!(
+            "Failed to remember the server of {}: {:#}",
+            config_path,
+            error
+        );
+    }
+
+    Ok(config_path)
+}
+
+#[tauri::command]
+#[tracing::instrument(skip(payload, state), fields(config_path = %payload.config_path))]
+pub async fn refresh_server_config<'a>(
+    payload: ServerRefreshPayload,
+    state: tauri::State<'a, MyState>,
+) -> Result<ServerRefreshResult, String> {
+    let source = match state.settings.config(&payload.config_path).server {
+        Some(source) => source,
+        None => return Err("The config was not downloaded from a server".to_string()),
+    };
+
+    let server = match ProfileServer::new(&source.url, &source.username, &payload.password) {
+        Ok(server) => server,
+        Err(error) => return Err(format!("{:#}", error)),
+    };
+
+    let content = match server.download(source.profile_type).await {
+        Ok(content) => content,
+        Err(error) => return Err(format!("{:#}", error)),
+    };
+
+    let hash = directory::content_hash(content.as_bytes());
+    if hash == source.hash {
+        return Ok(ServerRefreshResult {
+            config_path: payload.config_path,
+            updated: false,
+        });
+    }
+
+    let prepared = prepare_pasted_content(&content);
+    if !prepared.report.valid {
+        return Err(prepared.report.error_summary());
+    }
+
+    let name = match state.openvpn3.get_config(&payload.config_path).await {
+        Ok(config) => config.name,
+        Err(error) => return Err(format!("{:#}", error)),
+    };
+
+    let replaced = replace::replace_config(
+        &state.openvpn3,
+        &state.settings,
+        &payload.config_path,
+        &name,
+        prepared.content,
+        |config| config.server = Some(ServerSource { hash, ..source }),
+    )
+    .await;
+
+    match replaced {
+        Ok(config_path) => Ok(ServerRefreshResult {
+            config_path,
+            updated: true,
+        }),
+        Err(error) => {
+            tracing::error!("Failed to refresh {}: {:#}", payload.config_path, error);
+            Err(format!("{:#}", error))
+        }
+    }
+}
+
+#[tauri::command]
+#[tracing::instrument(skip(state))]
+pub fn get_watched_directories(state: tauri::State<'_, MyState>) -> Vec<WatchedDirectory> {
+    state.settings.get().watched_directories
+}
+
+#[tauri::command]
+#[tracing::instrument(skip(state))]
+pub fn set_watched_directories(
+    payload: Vec<WatchedDirectory>,
+    state: tauri::State<'_, MyState>,
+) -> Result<(), String> {
+    if let Some(missing) = payload
+        .iter()
+        .find(|directory| !Path::new(&directory.path).is_dir())
+    {
+        return Err(format!("{} is not a directory", missing.path));
+    }
+
+    if let Err(error) = state
+        .settings
+        .update(|settings| settings.watched_directories = payload)
+    {
+        return Err(format!("Failed to save settings: {:#}", error));
+    }
+
+    match state.directory_watcher.start() {
+        Ok(_) => Ok(()),
+        Err(error) => Err(format!("{:#}", error)),
+    }
+}
+
+#[tauri::command]
+#[tracing::instrument(skip(state))]
+pub async fn sync_watched_directories<'a>(
+    state: tauri::State<'a, MyState>,
+) -> Result<Vec<SyncReport>, String> {
+    match state.directory_watcher.sync_all().await {
+        Ok(reports) => Ok(reports),
+        Err(error) => Err(format!("{:#}", error)),
+    }
+}
+
+#[tauri::command]
+#[tracing::instrument(skip(state))]
+pub async fn get_networkmanager_connections<'a>(
+    state: tauri::State<'a, MyState>,
+) -> Result<Listing<NetworkManagerConnection>, String> {
+    let networkmanager = NetworkManagerDbus::new(state.openvpn3.connection());
+
+    match networkmanager.list_openvpn_connections().await {
+        Ok(connections) => Ok(connections),
+        Err(error) => Err(format!("{:#}", error)),
+    }
+}
+
+#[tauri::command]
+#[tracing::instrument(skip(state))]
+pub async fn import_networkmanager_connection<'a>(
+    payload: ImportNetworkManagerPayload,
+    state: tauri::State<'a, MyState>,
+) -> Result<NetworkManagerImportResult, String> {
+    let connection = match (&payload.keyfile, &payload.connection_path) {
+        (Some(keyfile), _) => ovpn::networkmanager::read_keyfile(Path::new(keyfile)),
+        (None, Some(connection_path)) => {
+            NetworkManagerDbus::new(state.openvpn3.connection())
+                .get_openvpn_connection(connection_path)
+                .await
         }
+        (None, None) => return Err("Either a keyfile or a connection is required".to_string()),
+    };
+
+    let conversion = match connection.and_then(|connection| {
+        ovpn::networkmanager::convert(&connection, |file| std::fs::read(file))
+    }) {
+        Ok(conversion) => conversion,
+        Err(error) => return Err(format!("{:#}", error)),
     };
-    Ok(path)
+
+    for unmapped in conversion.unmapped.iter() {
+        tracing::info!(
+            "Could not map {} of {}: {}",
+            unmapped.option,
+            conversion.name,
+            unmapped.reason
+        );
+    }
+
+    if !conversion.prepared.report.valid {
+        let summary = conversion.prepared.report.error_summary();
+        tracing::warn!("Refusing to import invalid config:\n{}", summary);
+        return Err(summary);
+    }
+
+    let config_name = payload
+        .config_name
+        .filter(|name| !name.is_empty())
+        .unwrap_or(conversion.name);
+
+    match state
+        .openvpn3
+        .import_config(
+            config_name,
+            conversion.prepared.content.clone(),
+            payload.single_use,
+            payload.persistent,
+        )
+        .await
+    {
+        Ok(config_path) => {
+            directory::record_content_hash(
+                &state.settings,
+                &config_path,
+                &conversion.prepared.content,
+            );
+            Ok(NetworkManagerImportResult {
+                config_path,
+                report: conversion.prepared.report,
+                unmapped: conversion.unmapped,
+            })
+        }
+        Err(error) => {
+            tracing::error!("Failed to import config: {:#}", error);
+            Err(format!("Failed to import config: {:#}", error))
+        }
+    }
 }
 
 #[tauri::command]
+#[tracing::instrument(skip(state))]
 pub async fn get_openvpn3_sessions<'a>(
     state: tauri::State<'a, MyState>,
-) -> Result<Vec<OpenVPN3Session>, ()> {
+) -> Result<Listing<OpenVPN3Session>, ()> {
     let openvpn3 = state.openvpn3.clone();
 
     let to_retry = move || {
         let openvpn3 = openvpn3.clone();
 
         Box::pin(async move { openvpn3.get_sessions().await })
-            as Pin<Box<dyn Future<Output = Result<Vec<OpenVPN3Session>, anyhow::Error>> + Send>>
+            as Pin<Box<dyn Future<Output = Result<Listing<OpenVPN3Session>, anyhow::Error>> + Send>>
     };
 
     if let Ok(sessions) = utils::async_retry(to_retry, 3).await {
@@ -83,8 +582,10 @@ pub async fn get_openvpn3_sessions<'a>(
 }
 
 #[tauri::command]
+#[tracing::instrument(skip(state))]
 pub async fn remove_config(payload: String, state: tauri::State<'_, MyState>) -> Result<(), ()> {
     let openvpn3 = state.openvpn3.clone();
+    let config_path = payload.clone();
 
     let to_retry = move || {
         let openvpn3 = openvpn3.clone();
@@ -95,6 +596,11 @@ pub async fn remove_config(payload: String, state: tauri::State<'_, MyState>) ->
     };
 
     if let Ok(_) = utils::async_retry(to_retry, 3).await {
+        if let Err(error) = state.settings.update(|settings| {
+            settings.configs.remove(&config_path);
+        }) {
+            tracing::warn!("Failed to forget settings of removed config: {:#}", error);
+        }
         return Ok(());
     } else {
         return Err(());
@@ -102,30 +608,300 @@ pub async fn remove_config(payload: String, state: tauri::State<'_, MyState>) ->
 }
 
 #[tauri::command]
+#[tracing::instrument(skip(state))]
 pub async fn new_tunnel<'a>(
     payload: String,
     state: tauri::State<'a, MyState>,
 ) -> Result<String, ()> {
-    let session_path = match state.openvpn3.new_tunnel(payload.clone()).await {
-        Ok(session_path) => session_path,
-        Err(_error) => {
-            println!("Failed to create new tunnel");
-            return Ok("".to_string());
+    match connection::start_tunnel(&state.openvpn3, &state.settings, &payload).await {
+        Ok(session_path) => Ok(session_path),
+        Err(error) => {
+            tracing::error!("Failed to create new tunnel: {:#}", error);
+            Ok("".to_string())
         }
+    }
+}
+
+/// Creates a session for a configuration, connects it and waits for it to
+/// come up, falling back to TCP if the configuration asks for it.
+#[tauri::command]
+#[tracing::instrument(skip(state))]
+pub async fn connect_config<'a>(
+    payload: String,
+    state: tauri::State<'a, MyState>,
+) -> Result<ConnectReport, String> {
+    match fallback::connect(&state.openvpn3, &state.settings, &payload).await {
+        Ok(report) => Ok(report),
+        Err(error) => Err(format!("{:#}", error)),
+    }
+}
+
+#[tauri::command]
+#[tracing::instrument(skip(state))]
+pub async fn get_config_protocol_fallback<'a>(
+    payload: String,
+    state: tauri::State<'a, MyState>,
+) -> Result<Option<ProtocolFallback>, ()> {
+    Ok(state.settings.config(&payload).protocol_fallback)
+}
+
+#[tauri::command]
+#[tracing::instrument(skip(state))]
+pub async fn set_config_protocol_fallback<'a>(
+    payload: ProtocolFallbackPayload,
+    state: tauri::State<'a, MyState>,
+) -> Result<(), String> {
+    match state
+        .settings
+        .update_config(&payload.config_path, |config| {
+            config.protocol_fallback = payload.fallback
+        }) {
+        Ok(_) => Ok(()),
+        Err(error) => Err(format!("{:#}", error)),
+    }
+}
+
+#[tauri::command]
+#[tracing::instrument(skip(state))]
+pub fn get_failover_groups(
+    state: tauri::State<'_, MyState>,
+) -> Result<Vec<FailoverGroupStatus>, ()> {
+    Ok(state
+        .settings
+        .get()
+        .failover_groups
+        .into_iter()
+        .map(|group| FailoverGroupStatus {
+            active: state.failover.active(&group.name),
+            group,
+        })
+        .collect())
+}
+
+#[tauri::command]
+#[tracing::instrument(skip(state))]
+pub fn set_failover_groups(
+    payload: Vec<FailoverGroup>,
+    state: tauri::State<'_, MyState>,
+) -> Result<(), String> {
+    for (index, group) in payload.iter().enumerate() {
+        if group.name.trim().is_empty() {
+            return Err("Every failover group needs a name".to_string());
+        }
+        if group.configs.is_empty() {
+            return Err(format!("{} has no configurations", group.name));
+        }
+        if payload[..index]
+            .iter()
+            .any(|other| other.name == group.name)
+        {
+            return Err(format!("There is more than one group named {}", group.name));
+        }
+    }
+
+    match state
+        .settings
+        .update(|settings| settings.failover_groups = payload)
+    {
+        Ok(_) => Ok(()),
+        Err(error) => Err(format!("Failed to save settings: {:#}", error)),
+    }
+}
+
+#[tauri::command]
+#[tracing::instrument(skip(state))]
+pub async fn connect_failover_group<'a>(
+    payload: String,
+    state: tauri::State<'a, MyState>,
+) -> Result<ActiveGroup, String> {
+    match state.failover.connect(&payload).await {
+        Ok(active) => Ok(active),
+        Err(error) => Err(format!("{:#}", error)),
+    }
+}
+
+#[tauri::command]
+#[tracing::instrument(skip(state))]
+pub async fn disconnect_failover_group<'a>(
+    payload: String,
+    state: tauri::State<'a, MyState>,
+) -> Result<(), String> {
+    match state.failover.disconnect(&payload).await {
+        Ok(_) => Ok(()),
+        Err(error) => Err(format!("{:#}", error)),
+    }
+}
+
+#[tauri::command]
+#[tracing::instrument(skip(state))]
+pub fn get_connection_sets(state: tauri::State<'_, MyState>) -> Vec<ConnectionSet> {
+    state.settings.get().connection_sets
+}
+
+#[tauri::command]
+#[tracing::instrument(skip(app, state))]
+pub fn set_connection_sets(
+    payload: Vec<ConnectionSet>,
+    app: tauri::AppHandle,
+    state: tauri::State<'_, MyState>,
+) -> Result<(), String> {
+    for (index, set) in payload.iter().enumerate() {
+        if set.name.trim().is_empty() {
+            return Err("Every connection set needs a name".to_string());
+        }
+        if set.configs.is_empty() {
+            return Err(format!("{} has no configurations", set.name));
+        }
+        if payload[..index].iter().any(|other| other.name == set.name) {
+            return Err(format!("There is more than one set named {}", set.name));
+        }
+    }
+
+    if let Err(error) = state
+        .settings
+        .update(|settings| settings.connection_sets = payload.clone())
+    {
+        return Err(format!("Failed to save settings: {:#}", error));
+    }
+
+    tray::refresh(&app, &payload);
+    Ok(())
+}
+
+#[tauri::command]
+#[tracing::instrument(skip(state))]
+pub async fn connect_connection_set<'a>(
+    payload: String,
+    state: tauri::State<'a, MyState>,
+) -> Result<SetReport, String> {
+    let set = match sets::find(&state.settings, &payload) {
+        Ok(set) => set,
+        Err(error) => return Err(format!("{:#}", error)),
     };
 
-    Ok(session_path)
+    match sets::connect(&state.openvpn3, &state.settings, &set).await {
+        Ok(report) => Ok(report),
+        Err(error) => Err(format!("{:#}", error)),
+    }
 }
 
 #[tauri::command]
+#[tracing::instrument(skip(state))]
+pub async fn disconnect_connection_set<'a>(
+    payload: String,
+    state: tauri::State<'a, MyState>,
+) -> Result<Vec<String>, String> {
+    let set = match sets::find(&state.settings, &payload) {
+        Ok(set) => set,
+        Err(error) => return Err(format!("{:#}", error)),
+    };
+
+    match sets::disconnect(&state.openvpn3, &set).await {
+        Ok(disconnected) => {
+            for session_path in disconnected.iter() {
+                state.failover.release(session_path);
+            }
+            Ok(disconnected)
+        }
+        Err(error) => Err(format!("{:#}", error)),
+    }
+}
+
+#[tauri::command]
+#[tracing::instrument(skip(state))]
+pub async fn get_config_remotes<'a>(
+    payload: String,
+    state: tauri::State<'a, MyState>,
+) -> Result<ConfigRemotes, String> {
+    let remotes = match state.openvpn3.get_remotes(&payload).await {
+        Ok(remotes) => remotes,
+        Err(error) => return Err(format!("{:#}", error)),
+    };
+
+    let config = state.settings.config(&payload);
+    let selected = config.remote.filter(|remote| remotes.contains(remote));
+
+    Ok(ConfigRemotes {
+        remotes,
+        selected,
+        auto_select: config.auto_select_remote,
+    })
+}
+
+/// Probes the remotes of a configuration and returns them fastest first.
+#[tauri::command]
+#[tracing::instrument(skip(state))]
+pub async fn rank_config_remotes<'a>(
+    payload: String,
+    state: tauri::State<'a, MyState>,
+) -> Result<Vec<RemoteLatency>, String> {
+    let remotes = match state.openvpn3.get_remotes(&payload).await {
+        Ok(remotes) => remotes,
+        Err(error) => return Err(format!("{:#}", error)),
+    };
+
+    Ok(latency::rank(&remotes, latency::PROBE_TIMEOUT).await)
+}
+
+#[tauri::command]
+#[tracing::instrument(skip(state))]
+pub async fn set_config_auto_select_remote<'a>(
+    payload: ConfigAutoSelectPayload,
+    state: tauri::State<'a, MyState>,
+) -> Result<(), String> {
+    match state
+        .settings
+        .update_config(&payload.config_path, |config| {
+            config.auto_select_remote = payload.enabled
+        }) {
+        Ok(_) => Ok(()),
+        Err(error) => Err(format!("{:#}", error)),
+    }
+}
+
+/// Connects to the given remote of a configuration and remembers it for
+/// the next connections.
+#[tauri::command]
+#[tracing::instrument(skip(state))]
+pub async fn connect_to_remote<'a>(
+    payload: ConnectRemotePayload,
+    state: tauri::State<'a, MyState>,
+) -> Result<String, String> {
+    if let Err(error) = state
+        .openvpn3
+        .select_remote(&payload.config_path, payload.remote.as_ref())
+        .await
+    {
+        return Err(format!("{:#}", error));
+    }
+
+    let remote = payload.remote.clone();
+    if let Err(error) = state
+        .settings
+        .update_config(&payload.config_path, |config| config.remote = remote)
+    {
+        tracing::warn!("Failed to remember the chosen remote: {:#}", error);
+    }
+
+    let log_verbosity = state.settings.config(&payload.config_path).log_verbosity;
+    match connection::create_session(&state.openvpn3, &payload.config_path, log_verbosity).await {
+        Ok(session_path) => Ok(session_path),
+        Err(error) => Err(format!("{:#}", error)),
+    }
+}
+
+#[tauri::command]
+#[tracing::instrument(skip(state))]
 pub async fn disconnect_session<'a>(
     payload: String,
     state: tauri::State<'a, MyState>,
 ) -> Result<(), ()> {
+    state.failover.release(&payload);
+
     match state.openvpn3.disconnect_session(payload.clone()).await {
         Ok(session_path) => session_path,
-        Err(_) => {
-            println!("Failed to disconnect session");
+        Err(error) => {
+            tracing::error!("Failed to disconnect session: {:#}", error);
             return Ok(());
         }
     };
@@ -134,14 +910,15 @@ pub async fn disconnect_session<'a>(
 }
 
 #[tauri::command]
+#[tracing::instrument(skip(state))]
 pub async fn connect_session<'a>(
     payload: String,
     state: tauri::State<'a, MyState>,
 ) -> Result<(), ()> {
     match state.openvpn3.connect_session(payload.clone()).await {
         Ok(session_path) => session_path,
-        Err(_) => {
-            println!("Failed to connect session");
+        Err(error) => {
+            tracing::error!("Failed to connect session: {:#}", error);
             return Ok(());
         }
     };
@@ -150,6 +927,459 @@ pub async fn connect_session<'a>(
 }
 
 #[tauri::command]
+#[tracing::instrument(skip(state))]
+pub async fn get_session_log_verbosity<'a>(
+    payload: String,
+    state: tauri::State<'a, MyState>,
+) -> Result<u32, String> {
+    match state.openvpn3.get_log_verbosity(payload).await {
+        Ok(log_verbosity) => Ok(log_verbosity),
+        Err(error) => Err(format!("{:#}", error)),
+    }
+}
+
+#[tauri::command]
+#[tracing::instrument(skip(state))]
+pub async fn set_session_log_verbosity<'a>(
+    payload: SessionLogVerbosityPayload,
+    state: tauri::State<'a, MyState>,
+) -> Result<(), String> {
+    if payload.log_verbosity > LOG_VERBOSITY_MAX {
+        return Err(format!(
+            "Log verbosity must be between 0 and {}",
+            LOG_VERBOSITY_MAX
+        ));
+    }
+
+    match state
+        .openvpn3
+        .set_log_verbosity(payload.session_path, payload.log_verbosity)
+        .await
+    {
+        Ok(_) => Ok(()),
+        Err(error) => Err(format!("{:#}", error)),
+    }
+}
+
+#[tauri::command]
+#[tracing::instrument(skip(state))]
+pub async fn set_session_log_forward<'a>(
+    payload: SessionLogForwardPayload,
+    state: tauri::State<'a, MyState>,
+) -> Result<(), String> {
+    match state
+        .openvpn3
+        .set_log_forward(payload.session_path, payload.enabled)
+        .await
+    {
+        Ok(_) => Ok(()),
+        Err(error) => Err(format!("{:#}", error)),
+    }
+}
+
+#[tauri::command]
+#[tracing::instrument(skip(state))]
+pub fn get_config_log_verbosity(payload: String, state: tauri::State<'_, MyState>) -> Option<u32> {
+    state.settings.config(&payload).log_verbosity
+}
+
+#[tauri::command]
+#[tracing::instrument(skip(state))]
+pub fn set_config_log_verbosity(
+    payload: ConfigLogVerbosityPayload,
+    state: tauri::State<'_, MyState>,
+) -> Result<(), String> {
+    if payload
+        .log_verbosity
+        .is_some_and(|level| level > LOG_VERBOSITY_MAX)
+    {
+        return Err(format!(
+            "Log verbosity must be between 0 and {}",
+            LOG_VERBOSITY_MAX
+        ));
+    }
+
+    let result = state
+        .settings
+        .update_config(&payload.config_path, |config| {
+            config.log_verbosity = payload.log_verbosity;
+        });
+
+    match result {
+        Ok(_) => Ok(()),
+        Err(error) => Err(format!("Failed to save settings: {:#}", error)),
+    }
+}
+
+#[tauri::command]
+#[tracing::instrument(skip(state))]
+pub fn get_signal_scope(state: tauri::State<'_, MyState>) -> SignalScope {
+    state.settings.get().signal_scope
+}
+
+#[tauri::command]
+#[tracing::instrument(skip(state))]
+pub fn set_signal_scope(
+    payload: SignalScope,
+    state: tauri::State<'_, MyState>,
+) -> Result<(), String> {
+    state.openvpn3.set_signal_scope(payload);
+
+    match state
+        .settings
+        .update(|settings| settings.signal_scope = payload)
+    {
+        Ok(_) => Ok(()),
+        Err(error) => Err(format!("Failed to save settings: {:#}", error)),
+    }
+}
+
+#[tauri::command]
+#[tracing::instrument(skip(state))]
+pub async fn get_config_summary<'a>(
+    payload: String,
+    state: tauri::State<'a, MyState>,
+) -> Result<ConfigSummary, String> {
+    match state.openvpn3.fetch_config(&payload).await {
+        Ok(content) => {
+            let (config, _) = ovpn::parser::parse(&content);
+            Ok(ovpn::summary::summarize(&config))
+        }
+        Err(error) => Err(format!("{:#}", error)),
+    }
+}
+
+#[tauri::command]
+#[tracing::instrument(skip(state, window))]
+pub async fn rename_config<'a>(
+    payload: RenameConfigPayload,
+    state: tauri::State<'a, MyState>,
+    window: tauri::Window,
+) -> Result<OpenVPN3Config, String> {
+    let name = payload.name.trim();
+    if name.is_empty() {
+        return Err("The name cannot be empty".to_string());
+    }
+
+    let configs = match state.openvpn3.get_configs().await {
+        Ok(configs) => configs,
+        Err(error) => return Err(format!("{:#}", error)),
+    };
+
+    // The name of a configuration that could not be read might be the same
+    if !configs.errors.is_empty() {
+        let unreadable: Vec<&str> = configs
+            .errors
+            .iter()
+            .map(|error| error.path.as_str())
+            .collect();
+        return Err(format!(
+            "Cannot check whether the name is in use, failed to read {}",
+            unreadable.join(", ")
+        ));
+    }
+
+    if configs
+        .items
+        .iter()
+        .any(|config| config.path != payload.config_path && config.name == name)
+    {
+        return Err(format!("A configuration named {} already exists", name));
+    }
+
+    if let Err(error) = state
+        .openvpn3
+        .rename_config(&payload.config_path, name)
+        .await
+    {
+        return Err(format!("{:#}", error));
+    }
+
+    let config = match state.openvpn3.get_config(&payload.config_path).await {
+        Ok(config) => config,
+        Err(error) => return Err(format!("{:#}", error)),
+    };
+
+    if let Err(error) = window.emit("config_changed", &config) {
+        tracing::warn!("Failed to emit config_changed: {:?}", error);
+    }
+
+    Ok(config)
+}
+
+#[tauri::command]
+#[tracing::instrument(skip(state))]
+pub async fn get_config_access<'a>(
+    payload: String,
+    state: tauri::State<'a, MyState>,
+) -> Result<ConfigAccess, String> {
+    match state.openvpn3.get_config(&payload).await {
+        Ok(config) => Ok(ConfigAccess::from_config(&config).await),
+        Err(error) => Err(format!("{:#}", error)),
+    }
+}
+
+#[tauri::command]
+#[tracing::instrument(skip(state, window))]
+pub async fn grant_config_access<'a>(
+    payload: ConfigUserPayload,
+    state: tauri::State<'a, MyState>,
+    window: tauri::Window,
+) -> Result<ConfigAccess, String> {
+    let uid = match access::resolve_uid(&payload.user).await {
+        Ok(uid) => uid,
+        Err(error) => return Err(format!("{:#}", error)),
+    };
+
+    if let Err(error) = state.openvpn3.grant_access(&payload.config_path, uid).await {
+        return Err(format!("{:#}", error));
+    }
+
+    changed_access(&state, &window, &payload.config_path).await
+}
+
+#[tauri::command]
+#[tracing::instrument(skip(state, window))]
+pub async fn revoke_config_access<'a>(
+    payload: ConfigUserPayload,
+    state: tauri::State<'a, MyState>,
+    window: tauri::Window,
+) -> Result<ConfigAccess, String> {
+    let uid = match access::resolve_uid(&payload.user).await {
+        Ok(uid) => uid,
+        Err(error) => return Err(format!("{:#}", error)),
+    };
+
+    if let Err(error) = state
+        .openvpn3
+        .revoke_access(&payload.config_path, uid)
+        .await
+    {
+        return Err(format!("{:#}", error));
+    }
+
+    changed_access(&state, &window, &payload.config_path).await
+}
+
+#[tauri::command]
+#[tracing::instrument(skip(state, window))]
+pub async fn set_config_public_access<'a>(
+    payload: ConfigFlagPayload,
+    state: tauri::State<'a, MyState>,
+    window: tauri::Window,
+) -> Result<ConfigAccess, String> {
+    if let Err(error) = state
+        .openvpn3
+        .set_config_flag(&payload.config_path, "public_access", payload.enabled)
+        .await
+    {
+        return Err(format!("{:#}", error));
+    }
+
+    changed_access(&state, &window, &payload.config_path).await
+}
+
+#[tauri::command]
+#[tracing::instrument(skip(state, window))]
+pub async fn set_config_locked_down<'a>(
+    payload: ConfigFlagPayload,
+    state: tauri::State<'a, MyState>,
+    window: tauri::Window,
+) -> Result<ConfigAccess, String> {
+    if let Err(error) = state
+        .openvpn3
+        .set_config_flag(&payload.config_path, "locked_down", payload.enabled)
+        .await
+    {
+        return Err(format!("{:#}", error));
+    }
+
+    changed_access(&state, &window, &payload.config_path).await
+}
+
+#[tauri::command]
+#[tracing::instrument(skip(state, window))]
+pub async fn seal_config<'a>(
+    payload: SealConfigPayload,
+    state: tauri::State<'a, MyState>,
+    window: tauri::Window,
+) -> Result<ConfigAccess, String> {
+    if !payload.confirm {
+        return Err("Sealing a configuration cannot be undone, it has to be confirmed".to_string());
+    }
+
+    if let Err(error) = state.openvpn3.seal_config(&payload.config_path).await {
+        return Err(format!("{:#}", error));
+    }
+
+    changed_access(&state, &window, &payload.config_path).await
+}
+
+/// Reads the configuration back after its access changed and tells the
+/// window about it.
+async fn changed_access(
+    state: &MyState,
+    window: &tauri::Window,
+    config_path: &str,
+) -> Result<ConfigAccess, String> {
+    let config = match state.openvpn3.get_config(config_path).await {
+        Ok(config) => config,
+        Err(error) => return Err(format!("{:#}", error)),
+    };
+
+    if let Err(error) = window.emit("config_changed", &config) {
+        tracing::warn!("Failed to emit config_changed: {:?}", error);
+    }
+
+    Ok(ConfigAccess::from_config(&config).await)
+}
+
+#[tauri::command]
+#[tracing::instrument]
+pub fn get_override_definitions() -> Vec<OverrideDefinition> {
+    overrides::OVERRIDES.to_vec()
+}
+
+#[tauri::command]
+#[tracing::instrument(skip(state))]
+pub async fn get_config_overrides<'a>(
+    payload: String,
+    state: tauri::State<'a, MyState>,
+) -> Result<Vec<ConfigOverride>, String> {
+    match state.openvpn3.get_overrides(&payload).await {
+        Ok(overrides) => Ok(overrides),
+        Err(error) => Err(format!("{:#}", error)),
+    }
+}
+
+/// Sets an override, or unsets it when the payload has no value, and
+/// returns the overrides of the configuration afterwards.
+#[tauri::command]
+#[tracing::instrument(skip(state))]
+pub async fn set_config_override<'a>(
+    payload: ConfigOverridePayload,
+    state: tauri::State<'a, MyState>,
+) -> Result<Vec<ConfigOverride>, String> {
+    let result = match &payload.value {
+        Some(value) => {
+            state
+                .openvpn3
+                .set_override(&payload.config_path, &payload.name, value)
+                .await
+        }
+        None => {
+            state
+                .openvpn3
+                .unset_override(&payload.config_path, &payload.name)
+                .await
+        }
+    };
+
+    if let Err(error) = result {
+        return Err(format!("{:#}", error));
+    }
+
+    match state.openvpn3.get_overrides(&payload.config_path).await {
+        Ok(overrides) => Ok(overrides),
+        Err(error) => Err(format!("{:#}", error)),
+    }
+}
+
+#[tauri::command]
+#[tracing::instrument(skip(state))]
+pub async fn export_config<'a>(
+    payload: ExportConfigPayload,
+    state: tauri::State<'a, MyState>,
+) -> Result<Vec<String>, String> {
+    let content = match state.openvpn3.fetch_config(&payload.config_path).await {
+        Ok(content) => content,
+        Err(error) => return Err(format!("{:#}", error)),
+    };
+
+    let (mut config, _) = ovpn::parser::parse(&content);
+    let removed = if payload.strip_private_keys {
+        ovpn::secrets::strip_private_keys(&mut config)
+    } else {
+        vec![]
+    };
+
+    let has_secrets = config
+        .blocks()
+        .any(|block| ovpn::secrets::is_private(&block.tag));
+    let content = if removed.is_empty() {
+        content
+    } else {
+        config.to_string()
+    };
+
+    // Keep exports that still contain private keys readable only by the user
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    if has_secrets {
+        options.mode(0o600);
+    }
+
+    // The mode only applies to new files, an existing file keeps its own
+    let result = options.open(&payload.file).and_then(|mut file| {
+        if has_secrets {
+            file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+        }
+        file.write_all(content.as_bytes())
+    });
+
+    match result {
+        Ok(_) => Ok(removed),
+        Err(error) => Err(format!("Failed to write {}: {}", payload.file, error)),
+    }
+}
+
+#[tauri::command]
+#[tracing::instrument(skip(state))]
+pub async fn export_logs<'a>(
+    payload: ExportLogsPayload,
+    state: tauri::State<'a, MyState>,
+) -> Result<usize, String> {
+    let records =
+        state
+            .log_history
+            .query(payload.session_path.as_deref(), payload.from, payload.to);
+
+    let file = match std::fs::File::create(&payload.file) {
+        Ok(file) => file,
+        Err(error) => {
+            return Err(format!("Failed to create {}: {}", payload.file, error));
+        }
+    };
+
+    let mut writer = std::io::BufWriter::new(file);
+    if let Err(error) = logs::export::write_records(&mut writer, &records, payload.format) {
+        return Err(format!("Failed to export logs: {:#}", error));
+    }
+
+    Ok(records.len())
+}
+
+#[tauri::command]
+#[tracing::instrument(skip(state))]
+pub async fn generate_diagnostics_bundle<'a>(
+    payload: String,
+    state: tauri::State<'a, MyState>,
+) -> Result<String, String> {
+    let path = std::path::Path::new(&payload);
+
+    match diagnostics::write_bundle(&state.openvpn3, &state.log_history, &state.settings, path)
+        .await
+    {
+        Ok(_) => Ok(payload),
+        Err(error) => Err(format!(
+            "Failed to generate diagnostics bundle: {:#}",
+            error
+        )),
+    }
+}
+
+#[tauri::command]
+#[tracing::instrument(skip(state, app_handle))]
 pub async fn exit_app<'a>(
     state: tauri::State<'a, MyState>,
     app_handle: tauri::AppHandle,
@@ -160,6 +1390,7 @@ pub async fn exit_app<'a>(
 }
 
 #[tauri::command]
+#[tracing::instrument(skip(window))]
 pub async fn minimize_to_tray(window: tauri::Window) -> Result<String, String> {
     window.hide().unwrap();
     Ok("Minimizng".to_string())
@@ -0,0 +1,208 @@
+pub mod failover;
+pub mod fallback;
+pub mod latency;
+pub mod sets;
+
+use std::time::Duration;
+
+use anyhow::Result;
+use serde::Serialize;
+use tokio::time::{sleep, Instant};
+
+use crate::dbus::codes::{
+    STATUS_MINOR_CONN_AUTH_FAILED, STATUS_MINOR_CONN_CONNECTED, STATUS_MINOR_CONN_DISCONNECTED,
+    STATUS_MINOR_CONN_DONE, STATUS_MINOR_CONN_FAILED, STATUS_MINOR_SESS_AUTH_CHALLENGE,
+    STATUS_MINOR_SESS_AUTH_URL, STATUS_MINOR_SESS_AUTH_USERPASS,
+};
+use crate::dbus::openvpn3::OpenVPN3Dbus;
+use crate::dbus::structs::OpenVPN3Session;
+use crate::settings::SettingsStore;
+
+const STATUS_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How an attempt to bring a session up ended.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum ConnectOutcome {
+    Connected,
+    Failed {
+        message: String,
+    },
+    AuthFailed {
+        message: String,
+    },
+    /// The session waits for credentials or a web authentication
+    NeedsInput,
+    TimedOut,
+}
+
+impl ConnectOutcome {
+    /// What the status of a session means for an attempt to connect it,
+    /// none while the session is still on its way.
+    pub fn from_status(session: &OpenVPN3Session) -> Option<Self> {
+        match session.minor_code {
+            STATUS_MINOR_CONN_CONNECTED => Some(ConnectOutcome::Connected),
+            STATUS_MINOR_CONN_AUTH_FAILED => Some(ConnectOutcome::AuthFailed {
+                message: session.status_message.clone(),
+            }),
+            STATUS_MINOR_CONN_FAILED | STATUS_MINOR_CONN_DISCONNECTED | STATUS_MINOR_CONN_DONE => {
+                Some(ConnectOutcome::Failed {
+                    message: session.status_message.clone(),
+                })
+            }
+            STATUS_MINOR_SESS_AUTH_USERPASS
+            | STATUS_MINOR_SESS_AUTH_CHALLENGE
+            | STATUS_MINOR_SESS_AUTH_URL => Some(ConnectOutcome::NeedsInput),
+            _ => None,
+        }
+    }
+
+    /// Whether the session is not going to connect, as opposed to being
+    /// connected or waiting for the user.
+    pub fn is_failure(&self) -> bool {
+        matches!(
+            self,
+            ConnectOutcome::Failed { .. }
+                | ConnectOutcome::AuthFailed { .. }
+                | ConnectOutcome::TimedOut
+        )
+    }
+}
+
+/// Creates a session for a configuration with the preferences stored for
+/// it: the remote to use and the log verbosity.
+pub async fn start_tunnel(
+    openvpn3: &OpenVPN3Dbus,
+    settings: &SettingsStore,
+    config_path: &str,
+) -> Result<String> {
+    let config = settings.config(config_path);
+
+    let remote = if config.auto_select_remote {
+        let remotes = openvpn3.get_remotes(config_path).await?;
+        let fastest = latency::fastest(&remotes).await;
+        if fastest.is_none() {
+            tracing::warn!("No remote of {} answered the latency probe", config_path);
+            // Do not stay on the remote picked for an earlier connection
+            openvpn3.select_remote(config_path, None).await?;
+        }
+        fastest
+    } else if let Some(remote) = config.remote.clone() {
+        // The configuration may have been imported again without it
+        let remotes = openvpn3.get_remotes(config_path).await?;
+        if remotes.contains(&remote) {
+            Some(remote)
+        } else {
+            tracing::warn!(
+                host = %remote.host,
+                port = remote.port,
+                proto = %remote.proto,
+                "Selected remote is no longer part of {}, letting openvpn3 choose",
+                config_path
+            );
+            if let Err(error) = settings.update_config(config_path, |config| config.remote = None) {
+                tracing::warn!("Failed to forget the selected remote: {:#}", error);
+            }
+            openvpn3.select_remote(config_path, None).await?;
+            None
+        }
+    } else {
+        None
+    };
+
+    // Set every time, as overrides are lost when a configuration is
+    // imported again
+    if let Some(remote) = &remote {
+        tracing::info!(
+            host = %remote.host,
+            port = remote.port,
+            proto = %remote.proto,
+            "Connecting to selected remote"
+        );
+        openvpn3.select_remote(config_path, Some(remote)).await?;
+    }
+
+    create_session(openvpn3, config_path, config.log_verbosity).await
+}
+
+/// Creates a session for a configuration as it is currently set up.
+pub async fn create_session(
+    openvpn3: &OpenVPN3Dbus,
+    config_path: &str,
+    log_verbosity: Option<u32>,
+) -> Result<String> {
+    let session_path = openvpn3.new_tunnel(config_path.to_string()).await?;
+
+    if let Some(log_verbosity) = log_verbosity {
+        if let Err(error) = openvpn3
+            .set_log_verbosity(session_path.clone(), log_verbosity)
+            .await
+        {
+            tracing::warn!("Failed to apply default log verbosity: {:#}", error);
+        }
+    }
+
+    Ok(session_path)
+}
+
+/// Connects a session and waits for the result, tearing the session down
+/// if it is not going anywhere.
+pub async fn connect_and_wait(
+    openvpn3: &OpenVPN3Dbus,
+    session_path: &str,
+    limit: Duration,
+) -> ConnectOutcome {
+    if let Err(error) = openvpn3.connect_session(session_path.to_string()).await {
+        return ConnectOutcome::Failed {
+            message: format!("{:#}", error),
+        };
+    }
+
+    let outcome = wait_for_connected(openvpn3, session_path, limit).await;
+
+    if outcome.is_failure() {
+        // A failed session may already be gone
+        let _ = openvpn3.disconnect_session(session_path.to_string()).await;
+    }
+
+    outcome
+}
+
+pub async fn session_status(
+    openvpn3: &OpenVPN3Dbus,
+    session_path: &str,
+) -> Result<OpenVPN3Session> {
+    let properties = openvpn3.get_session_properties(session_path).await?;
+    OpenVPN3Session::from_properties(session_path, &properties)
+}
+
+/// Polls the status of a session until it is connected, fails or `limit`
+/// runs out.
+pub async fn wait_for_connected(
+    openvpn3: &OpenVPN3Dbus,
+    session_path: &str,
+    limit: Duration,
+) -> ConnectOutcome {
+    let deadline = Instant::now() + limit;
+
+    loop {
+        match session_status(openvpn3, session_path).await {
+            Ok(session) => {
+                if let Some(outcome) = ConnectOutcome::from_status(&session) {
+                    return outcome;
+                }
+            }
+            // The session manager removes sessions that failed for good
+            Err(error) => {
+                return ConnectOutcome::Failed {
+                    message: format!("{:#}", error),
+                }
+            }
+        }
+
+        if Instant::now() >= deadline {
+            return ConnectOutcome::TimedOut;
+        }
+        sleep(STATUS_POLL_INTERVAL).await;
+    }
+}
@@ -0,0 +1,365 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, Instant};
+
+use crate::dbus::codes::{STATUS_MINOR_CONN_CONNECTED, STATUS_MINOR_CONN_RECONNECTING};
+use crate::dbus::openvpn3::OpenVPN3Dbus;
+use crate::settings::SettingsStore;
+
+use super::ConnectOutcome;
+
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+const MONITOR_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Configurations that provide the same network, connected one at a time.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FailoverGroup {
+    pub name: String,
+    /// Configuration paths, most preferred first
+    pub configs: Vec<String>,
+    /// How long a configuration may take to connect, and how long the
+    /// active one may stay disconnected before the group fails over
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_timeout_secs() -> u64 {
+    DEFAULT_TIMEOUT_SECS
+}
+
+impl FailoverGroup {
+    fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs)
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ActiveGroup {
+    pub group: String,
+    pub config_path: String,
+    pub session_path: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FailoverEvent {
+    pub group: String,
+    /// The configuration that dropped
+    pub from: Option<String>,
+    /// The configuration connected instead, none when every one failed
+    pub to: Option<String>,
+    pub message: String,
+}
+
+struct Monitored {
+    active: ActiveGroup,
+    monitor: JoinHandle<()>,
+}
+
+enum Tracked {
+    /// Reserved before the first configuration is tried, so that the group
+    /// is not connected twice at once. Numbered so that an attempt tells
+    /// its own reservation from that of a later one.
+    Connecting(u64),
+    Monitored(Monitored),
+}
+
+/// Connects failover groups and watches their active session, moving on
+/// to the next configuration of the group when it drops.
+pub struct FailoverManager {
+    openvpn3: Arc<OpenVPN3Dbus>,
+    settings: Arc<SettingsStore>,
+    event_sender: broadcast::Sender<FailoverEvent>,
+    active: Mutex<HashMap<String, Tracked>>,
+    attempts: AtomicU64,
+}
+
+impl FailoverManager {
+    pub fn new(openvpn3: Arc<OpenVPN3Dbus>, settings: Arc<SettingsStore>) -> Self {
+        let (event_sender, _) = broadcast::channel::<FailoverEvent>(16);
+
+        Self {
+            openvpn3,
+            settings,
+            event_sender,
+            active: Mutex::new(HashMap::new()),
+            attempts: AtomicU64::new(0),
+        }
+    }
+
+    /// Calls `callback` whenever a group fails over or gives up.
+    pub fn on_event<F>(&self, callback: F)
+    where
+        F: Fn(FailoverEvent) + Send + 'static,
+    {
+        let mut rx = self.event_sender.subscribe();
+
+        tokio::spawn(async move {
+            while let Ok(event) = rx.recv().await {
+                callback(event);
+            }
+        });
+    }
+
+    pub fn active(&self, group: &str) -> Option<ActiveGroup> {
+        self.active
+            .lock()
+            .unwrap()
+            .get(group)
+            .and_then(|tracked| match tracked {
+                Tracked::Monitored(monitored) => Some(monitored.active.clone()),
+                Tracked::Connecting(_) => None,
+            })
+    }
+
+    /// Connects the first configuration of the group that comes up and
+    /// starts watching it. A group that is still connecting is refused.
+    pub async fn connect(self: &Arc<Self>, name: &str) -> Result<ActiveGroup> {
+        let group = self.group(name)?;
+
+        let attempt = self.attempts.fetch_add(1, Ordering::Relaxed);
+        match self.active.lock().unwrap().entry(group.name.clone()) {
+            Entry::Occupied(entry) => {
+                return match entry.get() {
+                    Tracked::Monitored(monitored) => Ok(monitored.active.clone()),
+                    Tracked::Connecting(_) => Err(anyhow!("{} is already connecting", name)),
+                }
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(Tracked::Connecting(attempt));
+            }
+        }
+
+        let connected = self.connect_first(&group, None).await;
+
+        let unwanted = {
+            let mut tracked = self.active.lock().unwrap();
+            let reserved = tracked.get(&group.name);
+            if matches!(reserved, Some(Tracked::Connecting(reserved)) if *reserved == attempt) {
+                let active = match connected {
+                    Some(active) => active,
+                    None => {
+                        tracked.remove(&group.name);
+                        return Err(anyhow!("No configuration of {} connected", name));
+                    }
+                };
+
+                let manager = self.clone();
+                let group_name = group.name.clone();
+                let monitor = tokio::spawn(async move { manager.monitor(&group_name).await });
+
+                let replaced = tracked.insert(
+                    group.name.clone(),
+                    Tracked::Monitored(Monitored {
+                        active: active.clone(),
+                        monitor,
+                    }),
+                );
+                if let Some(Tracked::Monitored(replaced)) = replaced {
+                    replaced.monitor.abort();
+                }
+
+                return Ok(active);
+            }
+            connected
+        };
+
+        // Disconnected while connecting, the new session is not wanted
+        if let Some(active) = unwanted {
+            let _ = self.openvpn3.disconnect_session(active.session_path).await;
+        }
+        Err(anyhow!("{} was disconnected while connecting", name))
+    }
+
+    /// Stops watching the group and disconnects its session. A group that
+    /// is still connecting gives up once its current attempt is over.
+    pub async fn disconnect(&self, name: &str) -> Result<()> {
+        let tracked = self.active.lock().unwrap().remove(name);
+
+        match tracked {
+            Some(Tracked::Monitored(monitored)) => {
+                monitored.monitor.abort();
+                self.openvpn3
+                    .disconnect_session(monitored.active.session_path)
+                    .await
+            }
+            Some(Tracked::Connecting(_)) => Ok(()),
+            None => Err(anyhow!("{} is not connected", name)),
+        }
+    }
+
+    /// Stops watching the group `session_path` belongs to, if any, so that
+    /// disconnecting the session on purpose does not trigger a failover.
+    pub fn release(&self, session_path: &str) {
+        self.active
+            .lock()
+            .unwrap()
+            .retain(|_, tracked| match tracked {
+                Tracked::Monitored(monitored) if monitored.active.session_path == session_path => {
+                    monitored.monitor.abort();
+                    false
+                }
+                _ => true,
+            });
+    }
+
+    fn group(&self, name: &str) -> Result<FailoverGroup> {
+        self.settings
+            .get()
+            .failover_groups
+            .into_iter()
+            .find(|group| group.name == name)
+            .ok_or_else(|| anyhow!("There is no failover group named {}", name))
+    }
+
+    /// Connects the first configuration that comes up. After `dropped` went
+    /// down, the configurations following it are tried first and `dropped`
+    /// itself is skipped.
+    async fn connect_first(
+        &self,
+        group: &FailoverGroup,
+        dropped: Option<&str>,
+    ) -> Option<ActiveGroup> {
+        let start = dropped
+            .and_then(|dropped| group.configs.iter().position(|config| config == dropped))
+            .map_or(0, |position| position + 1);
+        let candidates = group.configs[start..]
+            .iter()
+            .chain(group.configs[..start].iter())
+            .filter(|config| Some(config.as_str()) != dropped);
+
+        for config_path in candidates {
+            tracing::info!(group = %group.name, "Trying {}", config_path);
+
+            let session_path =
+                match super::start_tunnel(&self.openvpn3, &self.settings, config_path).await {
+                    Ok(session_path) => session_path,
+                    Err(error) => {
+                        tracing::warn!(
+                            "Failed to create a session for {}: {:#}",
+                            config_path,
+                            error
+                        );
+                        continue;
+                    }
+                };
+
+            // A session waiting for credentials counts, the user is busy
+            // with it
+            let outcome =
+                super::connect_and_wait(&self.openvpn3, &session_path, group.timeout()).await;
+            match outcome {
+                ConnectOutcome::Connected | ConnectOutcome::NeedsInput => {
+                    return Some(ActiveGroup {
+                        group: group.name.clone(),
+                        config_path: config_path.clone(),
+                        session_path,
+                    })
+                }
+                outcome => {
+                    tracing::warn!(group = %group.name, ?outcome, "{} did not connect", config_path)
+                }
+            }
+        }
+
+        None
+    }
+
+    async fn monitor(&self, name: &str) {
+        let mut last_connected = Instant::now();
+
+        loop {
+            sleep(MONITOR_INTERVAL).await;
+
+            let (group, active) = match (self.group(name), self.active(name)) {
+                (Ok(group), Some(active)) => (group, active),
+                _ => return,
+            };
+
+            // openvpn3 reconnects on its own, which is given as long as
+            // connecting takes. Waiting for the user does not count. A
+            // session disconnected on purpose has been released already.
+            let status = super::session_status(&self.openvpn3, &active.session_path).await;
+            let dropped = match status {
+                Ok(session) if session.minor_code == STATUS_MINOR_CONN_CONNECTED => {
+                    last_connected = Instant::now();
+                    false
+                }
+                Ok(session) if session.minor_code == STATUS_MINOR_CONN_RECONNECTING => {
+                    last_connected.elapsed() > group.timeout()
+                }
+                Ok(session) => match ConnectOutcome::from_status(&session) {
+                    Some(ConnectOutcome::NeedsInput) => {
+                        last_connected = Instant::now();
+                        false
+                    }
+                    Some(outcome) if outcome.is_failure() => true,
+                    _ => last_connected.elapsed() > group.timeout(),
+                },
+                Err(_) => {
+                    last_connected.elapsed() > group.timeout()
+                        || self.vanished(&active.session_path).await
+                }
+            };
+
+            if !dropped {
+                continue;
+            }
+
+            tracing::warn!(group = %name, "{} dropped, failing over", active.config_path);
+            let _ = self
+                .openvpn3
+                .disconnect_session(active.session_path.clone())
+                .await;
+
+            let replacement = self.connect_first(&group, Some(&active.config_path)).await;
+            let event = FailoverEvent {
+                group: name.to_string(),
+                from: Some(active.config_path.clone()),
+                to: replacement
+                    .as_ref()
+                    .map(|active| active.config_path.clone()),
+                message: match &replacement {
+                    Some(replacement) => format!(
+                        "{}: switched from {} to {}",
+                        name, active.config_path, replacement.config_path
+                    ),
+                    None => format!("{}: no configuration could connect", name),
+                },
+            };
+            let _ = self.event_sender.send(event);
+
+            let mut monitored = self.active.lock().unwrap();
+            match replacement {
+                Some(replacement) => {
+                    if let Some(Tracked::Monitored(entry)) = monitored.get_mut(name) {
+                        entry.active = replacement;
+                    }
+                    last_connected = Instant::now();
+                }
+                None => {
+                    monitored.remove(name);
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Whether the session manager no longer lists the session.
+    async fn vanished(&self, session_path: &str) -> bool {
+        self.openvpn3
+            .get_session_paths()
+            .await
+            .is_ok_and(|paths| !paths.iter().any(|path| &**path == session_path))
+    }
+}
@@ -0,0 +1,243 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::dbus::openvpn3::OpenVPN3Dbus;
+use crate::dbus::overrides::{ConfigOverride, OverrideValue};
+use crate::settings::SettingsStore;
+
+use super::ConnectOutcome;
+
+const DEFAULT_TIMEOUT_SECS: u64 = 20;
+const FALLBACK_PORT: u16 = 443;
+
+/// Retrying over TCP when a configuration does not connect over UDP, for
+/// networks that block UDP.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProtocolFallback {
+    /// How long each attempt may take to reach Connected
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    /// Try TCP on port 443 as well when TCP on the configured port fails
+    #[serde(default)]
+    pub try_port_443: bool,
+    /// Keep the protocol and port of a fallback that connected for later
+    /// connections, instead of starting over with the configured ones
+    #[serde(default)]
+    pub keep_working_fallback: bool,
+}
+
+fn default_timeout_secs() -> u64 {
+    DEFAULT_TIMEOUT_SECS
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectAttempt {
+    /// Protocol and port forced for the attempt, none for the first one
+    pub proto: Option<String>,
+    pub port: Option<u16>,
+    pub session_path: String,
+    #[serde(flatten)]
+    pub outcome: ConnectOutcome,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectReport {
+    /// The connected session, or the one waiting for credentials
+    pub session_path: Option<String>,
+    pub attempts: Vec<ConnectAttempt>,
+    /// The fallback that connected, e.g. `TCP on port 443`
+    pub fallback: Option<String>,
+}
+
+impl ConnectReport {
+    fn record(&mut self, attempt: ConnectAttempt) {
+        self.session_path = match attempt.outcome {
+            ConnectOutcome::Connected | ConnectOutcome::NeedsInput => {
+                Some(attempt.session_path.clone())
+            }
+            _ => None,
+        };
+        self.attempts.push(attempt);
+    }
+}
+
+/// Connects a configuration and waits for the session to come up. When the
+/// configuration has a fallback policy and the session fails or times out,
+/// it is torn down and the connection is tried again over TCP, then over
+/// TCP on port 443 if the policy asks for it. The overrides used for the
+/// fallback are undone afterwards, unless the fallback connected and the
+/// policy keeps working fallbacks.
+pub async fn connect(
+    openvpn3: &OpenVPN3Dbus,
+    settings: &SettingsStore,
+    config_path: &str,
+) -> Result<ConnectReport> {
+    let config = settings.config(config_path);
+    let timeout = Duration::from_secs(
+        config
+            .protocol_fallback
+            .as_ref()
+            .map_or(DEFAULT_TIMEOUT_SECS, |policy| policy.timeout_secs),
+    );
+
+    let mut report = ConnectReport::default();
+
+    let session_path = super::start_tunnel(openvpn3, settings, config_path).await?;
+    let outcome = super::connect_and_wait(openvpn3, &session_path, timeout).await;
+    report.record(ConnectAttempt {
+        proto: None,
+        port: None,
+        session_path,
+        outcome: outcome.clone(),
+    });
+
+    let policy = match config.protocol_fallback {
+        Some(policy) if should_fall_back(&outcome) => policy,
+        _ => return Ok(report),
+    };
+
+    let saved = openvpn3.get_overrides(config_path).await?;
+
+    let mut steps = vec![];
+    if uses_udp(openvpn3, config_path, &saved).await {
+        steps.push(None);
+    }
+    if policy.try_port_443 {
+        steps.push(Some(FALLBACK_PORT));
+    }
+
+    for port in steps {
+        let description = match port {
+            Some(port) => format!("TCP on port {}", port),
+            None => "TCP".to_string(),
+        };
+        tracing::info!("Retrying {} over {}", config_path, description);
+
+        let result = fall_back(openvpn3, config_path, port, config.log_verbosity).await;
+        let session_path = match result {
+            Ok(session_path) => session_path,
+            Err(error) => {
+                tracing::error!("Failed to retry over {}: {:#}", description, error);
+                break;
+            }
+        };
+
+        let outcome = super::connect_and_wait(openvpn3, &session_path, timeout).await;
+        report.record(ConnectAttempt {
+            proto: Some("tcp".to_string()),
+            port,
+            session_path,
+            outcome: outcome.clone(),
+        });
+
+        if outcome == ConnectOutcome::Connected {
+            tracing::info!("{} connected over {}", config_path, description);
+            report.fallback = Some(description);
+        }
+        if !should_fall_back(&outcome) {
+            break;
+        }
+    }
+
+    if let Some(fallback) = report
+        .fallback
+        .as_ref()
+        .filter(|_| policy.keep_working_fallback)
+    {
+        tracing::info!(
+            "Keeping {} for later connections of {}",
+            fallback,
+            config_path
+        );
+    } else if let Err(error) = restore_overrides(openvpn3, config_path, &saved).await {
+        tracing::warn!(
+            "Failed to restore overrides of {}: {:#}",
+            config_path,
+            error
+        );
+    }
+
+    Ok(report)
+}
+
+async fn fall_back(
+    openvpn3: &OpenVPN3Dbus,
+    config_path: &str,
+    port: Option<u16>,
+    log_verbosity: Option<u32>,
+) -> Result<String> {
+    openvpn3
+        .set_override(
+            config_path,
+            "proto-override",
+            &OverrideValue::String("tcp".to_string()),
+        )
+        .await?;
+
+    if let Some(port) = port {
+        openvpn3
+            .set_override(
+                config_path,
+                "port-override",
+                &OverrideValue::String(port.to_string()),
+            )
+            .await?;
+    }
+
+    super::create_session(openvpn3, config_path, log_verbosity).await
+}
+
+/// Another protocol does not help against rejected credentials.
+fn should_fall_back(outcome: &ConnectOutcome) -> bool {
+    matches!(
+        outcome,
+        ConnectOutcome::Failed { .. } | ConnectOutcome::TimedOut
+    )
+}
+
+/// Whether the first attempt went over UDP, in which case TCP on the
+/// configured port is worth a try.
+async fn uses_udp(
+    openvpn3: &OpenVPN3Dbus,
+    config_path: &str,
+    overrides: &[ConfigOverride],
+) -> bool {
+    if let Some(proto) = overrides.iter().find(|set| set.name == "proto-override") {
+        return proto.value == OverrideValue::String("udp".to_string());
+    }
+
+    match openvpn3.get_remotes(config_path).await {
+        Ok(remotes) => remotes.iter().any(|remote| remote.proto == "udp"),
+        Err(_) => true,
+    }
+}
+
+async fn restore_overrides(
+    openvpn3: &OpenVPN3Dbus,
+    config_path: &str,
+    saved: &[ConfigOverride],
+) -> Result<()> {
+    let current = openvpn3.get_overrides(config_path).await?;
+
+    for name in ["proto-override", "port-override"] {
+        let before = saved.iter().find(|set| set.name == name);
+        let now = current.iter().find(|set| set.name == name);
+
+        match (before, now) {
+            (Some(before), _) => {
+                openvpn3
+                    .set_override(config_path, name, &before.value)
+                    .await?
+            }
+            (None, Some(_)) => openvpn3.unset_override(config_path, name).await?,
+            (None, None) => (),
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,202 @@
+use std::cmp::Ordering;
+use std::io::ErrorKind;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tokio::net::{lookup_host, TcpStream};
+use tokio::time::timeout;
+
+use crate::ovpn::remotes::RemoteServer;
+
+/// How long name resolution and the TCP handshake may each take.
+pub const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteLatency {
+    pub remote: RemoteServer,
+    /// The address that was probed
+    pub address: Option<String>,
+    pub dns_ms: Option<u64>,
+    pub connect_ms: Option<u64>,
+    pub error: Option<String>,
+}
+
+impl RemoteLatency {
+    pub fn reachable(&self) -> bool {
+        self.connect_ms.is_some()
+    }
+
+    fn total_ms(&self) -> Option<u64> {
+        Some(self.dns_ms? + self.connect_ms?)
+    }
+}
+
+/// Resolves the host of `remote` and times a TCP handshake with it.
+///
+/// UDP servers usually refuse TCP connections, but a refusal still takes a
+/// round trip, so it counts as a measurement.
+pub async fn probe(remote: &RemoteServer, limit: Duration) -> RemoteLatency {
+    let mut latency = RemoteLatency {
+        remote: remote.clone(),
+        address: None,
+        dns_ms: None,
+        connect_ms: None,
+        error: None,
+    };
+
+    let started = Instant::now();
+    let address = match timeout(limit, lookup_host((remote.host.as_str(), remote.port))).await {
+        Ok(Ok(mut addresses)) => addresses.next(),
+        Ok(Err(error)) => {
+            latency.error = Some(format!("Failed to resolve {}: {}", remote.host, error));
+            return latency;
+        }
+        Err(_) => {
+            latency.error = Some(format!("Resolving {} timed out", remote.host));
+            return latency;
+        }
+    };
+    latency.dns_ms = Some(started.elapsed().as_millis() as u64);
+
+    let address = match address {
+        Some(address) => address,
+        None => {
+            latency.error = Some(format!("{} has no address", remote.host));
+            return latency;
+        }
+    };
+    latency.address = Some(address.to_string());
+
+    let started = Instant::now();
+    match timeout(limit, TcpStream::connect(address)).await {
+        Ok(Ok(_)) => latency.connect_ms = Some(started.elapsed().as_millis() as u64),
+        Ok(Err(error)) if error.kind() == ErrorKind::ConnectionRefused => {
+            latency.connect_ms = Some(started.elapsed().as_millis() as u64)
+        }
+        Ok(Err(error)) => {
+            latency.error = Some(format!("Failed to connect to {}: {}", address, error))
+        }
+        Err(_) => latency.error = Some(format!("Connecting to {} timed out", address)),
+    }
+
+    latency
+}
+
+/// Probes all remotes at once and sorts them fastest first, unreachable
+/// remotes last.
+pub async fn rank(remotes: &[RemoteServer], limit: Duration) -> Vec<RemoteLatency> {
+    let mut ranked =
+        futures::future::join_all(remotes.iter().map(|remote| probe(remote, limit))).await;
+
+    ranked.sort_by(|a, b| match (a.total_ms(), b.total_ms()) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    });
+
+    ranked
+}
+
+/// The reachable remote with the lowest latency.
+pub async fn fastest(remotes: &[RemoteServer]) -> Option<RemoteServer> {
+    rank(remotes, PROBE_TIMEOUT)
+        .await
+        .into_iter()
+        .find(RemoteLatency::reachable)
+        .map(|latency| latency.remote)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tokio::net::{TcpListener, TcpSocket};
+
+    fn local(port: u16) -> RemoteServer {
+        RemoteServer {
+            host: "127.0.0.1".to_string(),
+            port,
+            proto: "tcp".to_string(),
+        }
+    }
+
+    /// A port nothing listens on, found by binding and closing a listener.
+    async fn closed_port() -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        listener.local_addr().unwrap().port()
+    }
+
+    #[tokio::test]
+    async fn measures_an_open_listener() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let latency = probe(&local(port), PROBE_TIMEOUT).await;
+
+        assert!(latency.reachable());
+        assert_eq!(latency.address, Some(format!("127.0.0.1:{}", port)));
+        assert!(latency.dns_ms.is_some());
+        assert_eq!(latency.error, None);
+    }
+
+    #[tokio::test]
+    async fn counts_a_refused_port_as_reachable() {
+        let latency = probe(&local(closed_port().await), PROBE_TIMEOUT).await;
+
+        assert!(latency.reachable());
+        assert_eq!(latency.error, None);
+    }
+
+    #[tokio::test]
+    async fn reports_a_timeout() {
+        // Once the accept queue is full, the handshakes of further
+        // connections are left unanswered
+        let socket = TcpSocket::new_v4().unwrap();
+        socket.bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let listener = socket.listen(0).unwrap();
+        let address = listener.local_addr().unwrap();
+        let mut queued = vec![];
+        for _ in 0..2 {
+            if let Ok(Ok(stream)) =
+                timeout(Duration::from_millis(200), TcpStream::connect(address)).await
+            {
+                queued.push(stream);
+            }
+        }
+
+        let latency = probe(&local(address.port()), Duration::from_millis(300)).await;
+
+        assert!(!latency.reachable());
+        assert_eq!(
+            latency.error,
+            Some(format!("Connecting to {} timed out", address))
+        );
+    }
+
+    #[tokio::test]
+    async fn ranks_unreachable_remotes_last() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let open = local(listener.local_addr().unwrap().port());
+        let refused = local(closed_port().await);
+        let unresolvable = RemoteServer {
+            host: "unresolvable.invalid".to_string(),
+            port: 1194,
+            proto: "udp".to_string(),
+        };
+
+        let ranked = rank(
+            &[unresolvable.clone(), open.clone(), refused.clone()],
+            PROBE_TIMEOUT,
+        )
+        .await;
+
+        let reachable: Vec<bool> = ranked.iter().map(RemoteLatency::reachable).collect();
+        assert_eq!(reachable, [true, true, false]);
+        assert_eq!(ranked[2].remote, unresolvable);
+        assert!(ranked[..2]
+            .iter()
+            .all(|latency| latency.remote == open || latency.remote == refused));
+    }
+}
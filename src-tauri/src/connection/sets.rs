@@ -0,0 +1,246 @@
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::dbus::openvpn3::OpenVPN3Dbus;
+use crate::settings::SettingsStore;
+
+use super::ConnectOutcome;
+
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+
+/// Configurations that are connected and disconnected together, e.g. the
+/// company network and a lab behind it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionSet {
+    pub name: String,
+    /// Configuration paths in the order they are connected, they are
+    /// disconnected in reverse
+    pub configs: Vec<String>,
+    #[serde(default)]
+    pub on_failure: SetFailurePolicy,
+    /// How long each configuration may take to connect
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_timeout_secs() -> u64 {
+    DEFAULT_TIMEOUT_SECS
+}
+
+/// What happens to the rest of a set when one of its configurations does
+/// not connect.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SetFailurePolicy {
+    /// Keep connecting the remaining configurations
+    Continue,
+    /// Leave the configurations connected so far, skip the rest
+    Stop,
+    /// Disconnect the configurations connected so far
+    #[default]
+    Rollback,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SetMember {
+    pub config_path: String,
+    pub session_path: Option<String>,
+    /// None when the configuration was skipped
+    #[serde(flatten)]
+    pub outcome: Option<ConnectOutcome>,
+    /// The configuration already had a session, which was left alone
+    pub already_connected: bool,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SetReport {
+    pub set: String,
+    pub members: Vec<SetMember>,
+    /// Sessions that were disconnected again because of the failure policy
+    pub rolled_back: Vec<String>,
+    /// One line for notifications, e.g. `Work: 1 of 2 configurations up`
+    pub summary: String,
+}
+
+impl SetReport {
+    /// Whether every configuration of the set is up or waiting for the user.
+    pub fn complete(&self) -> bool {
+        self.rolled_back.is_empty()
+            && self.members.iter().all(|member| {
+                member.already_connected
+                    || member
+                        .outcome
+                        .as_ref()
+                        .is_some_and(|outcome| !outcome.is_failure())
+            })
+    }
+
+    fn summarize(&mut self) {
+        let up = self
+            .members
+            .iter()
+            .filter(|member| {
+                !self
+                    .rolled_back
+                    .iter()
+                    .any(|session_path| member.session_path.as_ref() == Some(session_path))
+            })
+            .filter(|member| {
+                member.already_connected
+                    || member
+                        .outcome
+                        .as_ref()
+                        .is_some_and(|outcome| !outcome.is_failure())
+            })
+            .count();
+
+        let mut summary = format!(
+            "{}: {} of {} configurations up",
+            self.set,
+            up,
+            self.members.len()
+        );
+        if !self.rolled_back.is_empty() {
+            summary.push_str(", the rest was disconnected again");
+        }
+        self.summary = summary;
+    }
+}
+
+pub fn find(settings: &SettingsStore, name: &str) -> Result<ConnectionSet> {
+    settings
+        .get()
+        .connection_sets
+        .into_iter()
+        .find(|set| set.name == name)
+        .ok_or_else(|| anyhow!("There is no connection set named {}", name))
+}
+
+/// Connects the configurations of a set one after another, each waiting for
+/// the previous one, so that later configurations can rely on the networks
+/// of earlier ones. Configurations that already have a session are left as
+/// they are, a session waiting for credentials does not hold up the rest.
+pub async fn connect(
+    openvpn3: &OpenVPN3Dbus,
+    settings: &SettingsStore,
+    set: &ConnectionSet,
+) -> Result<SetReport> {
+    let limit = Duration::from_secs(set.timeout_secs);
+    let connected_configs = session_configs(openvpn3).await?;
+
+    let mut report = SetReport {
+        set: set.name.clone(),
+        members: vec![],
+        rolled_back: vec![],
+        summary: String::new(),
+    };
+    let mut failed = false;
+
+    for config_path in set.configs.iter() {
+        let mut member = SetMember {
+            config_path: config_path.clone(),
+            session_path: None,
+            outcome: None,
+            already_connected: false,
+        };
+
+        if failed && set.on_failure != SetFailurePolicy::Continue {
+            report.members.push(member);
+            continue;
+        }
+
+        if let Some((session_path, _)) = connected_configs
+            .iter()
+            .find(|(_, session_config)| session_config == config_path)
+        {
+            member.session_path = Some(session_path.clone());
+            member.already_connected = true;
+            report.members.push(member);
+            continue;
+        }
+
+        tracing::info!(set = %set.name, "Connecting {}", config_path);
+
+        let outcome = match super::start_tunnel(openvpn3, settings, config_path).await {
+            Ok(session_path) => {
+                let outcome = super::connect_and_wait(openvpn3, &session_path, limit).await;
+                member.session_path = Some(session_path);
+                outcome
+            }
+            Err(error) => ConnectOutcome::Failed {
+                message: format!("{:#}", error),
+            },
+        };
+
+        if outcome.is_failure() {
+            tracing::warn!(set = %set.name, ?outcome, "{} did not connect", config_path);
+            // connect_and_wait already tore the session down
+            member.session_path = None;
+            failed = true;
+        }
+        member.outcome = Some(outcome);
+        report.members.push(member);
+    }
+
+    if failed && set.on_failure == SetFailurePolicy::Rollback {
+        // Only what this call brought up, in reverse order
+        let started = report
+            .members
+            .iter()
+            .rev()
+            .filter(|member| !member.already_connected)
+            .filter_map(|member| member.session_path.clone())
+            .collect::<Vec<String>>();
+
+        for session_path in started {
+            match openvpn3.disconnect_session(session_path.clone()).await {
+                Ok(_) => report.rolled_back.push(session_path),
+                Err(error) => {
+                    tracing::error!("Failed to roll back {}: {:#}", session_path, error)
+                }
+            }
+        }
+    }
+
+    report.summarize();
+    Ok(report)
+}
+
+/// Disconnects every session of the configurations of a set, last
+/// configuration first. Returns the disconnected sessions.
+pub async fn disconnect(openvpn3: &OpenVPN3Dbus, set: &ConnectionSet) -> Result<Vec<String>> {
+    let connected_configs = session_configs(openvpn3).await?;
+    let mut disconnected = vec![];
+
+    for config_path in set.configs.iter().rev() {
+        for (session_path, _) in connected_configs
+            .iter()
+            .filter(|(_, session_config)| session_config == config_path)
+        {
+            match openvpn3.disconnect_session(session_path.clone()).await {
+                Ok(_) => disconnected.push(session_path.clone()),
+                Err(error) => {
+                    tracing::error!("Failed to disconnect {}: {:#}", session_path, error)
+                }
+            }
+        }
+    }
+
+    Ok(disconnected)
+}
+
+/// Session paths paired with the configuration they were started from.
+async fn session_configs(openvpn3: &OpenVPN3Dbus) -> Result<Vec<(String, String)>> {
+    let sessions = openvpn3.get_sessions().await?;
+
+    Ok(sessions
+        .items
+        .into_iter()
+        .filter_map(|session| Some((session.path, session.config_path?)))
+        .collect())
+}
@@ -1,2 +1,7 @@
+pub mod codes;
+pub mod json;
+pub mod networkmanager;
 pub mod openvpn3;
+pub mod overrides;
 pub mod structs;
+pub mod traced;
@@ -0,0 +1,98 @@
+// Numeric codes used by openvpn3-linux in StatusChange and Log signals.
+// See openvpn3-linux src/dbus/constants.hpp
+
+// Highest value accepted by a session's log_verbosity property
+pub const LOG_VERBOSITY_MAX: u32 = 6;
+
+pub const STATUS_MAJOR_CONNECTION: u32 = 2;
+pub const STATUS_MAJOR_SESSION: u32 = 3;
+
+pub const STATUS_MINOR_CONN_CONNECTED: u32 = 7;
+pub const STATUS_MINOR_CONN_DISCONNECTED: u32 = 9;
+pub const STATUS_MINOR_CONN_FAILED: u32 = 10;
+pub const STATUS_MINOR_CONN_AUTH_FAILED: u32 = 11;
+pub const STATUS_MINOR_CONN_RECONNECTING: u32 = 12;
+pub const STATUS_MINOR_CONN_DONE: u32 = 16;
+pub const STATUS_MINOR_SESS_REMOVED: u32 = 19;
+pub const STATUS_MINOR_SESS_AUTH_USERPASS: u32 = 20;
+pub const STATUS_MINOR_SESS_AUTH_CHALLENGE: u32 = 21;
+pub const STATUS_MINOR_SESS_AUTH_URL: u32 = 22;
+
+pub fn status_major_name(code: u32) -> &'static str {
+    match code {
+        0 => "UNSET",
+        1 => "CFG_ERROR",
+        2 => "CONNECTION",
+        3 => "SESSION",
+        4 => "PKCS11",
+        5 => "PROCESS",
+        _ => "UNKNOWN",
+    }
+}
+
+pub fn status_minor_name(code: u32) -> &'static str {
+    match code {
+        0 => "UNSET",
+        1 => "CFG_ERROR",
+        2 => "CFG_OK",
+        3 => "CFG_INLINE_MISSING",
+        4 => "CFG_REQUIRE_USER",
+        5 => "CONN_INIT",
+        6 => "CONN_CONNECTING",
+        7 => "CONN_CONNECTED",
+        8 => "CONN_DISCONNECTING",
+        9 => "CONN_DISCONNECTED",
+        10 => "CONN_FAILED",
+        11 => "CONN_AUTH_FAILED",
+        12 => "CONN_RECONNECTING",
+        13 => "CONN_PAUSING",
+        14 => "CONN_PAUSED",
+        15 => "CONN_RESUMING",
+        16 => "CONN_DONE",
+        17 => "SESS_NEW",
+        18 => "SESS_BACKEND_COMPLETED",
+        19 => "SESS_REMOVED",
+        20 => "SESS_AUTH_USERPASS",
+        21 => "SESS_AUTH_CHALLENGE",
+        22 => "SESS_AUTH_URL",
+        23 => "PKCS11_SIGN",
+        24 => "PKCS11_ENCRYPT",
+        25 => "PKCS11_DECRYPT",
+        26 => "PKCS11_VERIFY",
+        27 => "PROC_STARTED",
+        28 => "PROC_STOPPED",
+        29 => "PROC_KILLED",
+        _ => "UNKNOWN",
+    }
+}
+
+pub fn log_group_name(code: u32) -> &'static str {
+    match code {
+        0 => "UNDEFINED",
+        1 => "MASTERPROC",
+        2 => "CONFIGMGR",
+        3 => "SESSIONMGR",
+        4 => "BACKENDSTART",
+        5 => "LOGGER",
+        6 => "BACKENDPROC",
+        7 => "CLIENT",
+        8 => "NETCFG",
+        9 => "EXTSERVICE",
+        _ => "UNKNOWN",
+    }
+}
+
+pub fn log_category_name(code: u32) -> &'static str {
+    match code {
+        0 => "UNDEFINED",
+        1 => "DEBUG",
+        2 => "VERB2",
+        3 => "VERB1",
+        4 => "INFO",
+        5 => "WARN",
+        6 => "ERROR",
+        7 => "CRIT",
+        8 => "FATAL",
+        _ => "UNKNOWN",
+    }
+}
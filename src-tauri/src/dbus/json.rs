@@ -0,0 +1,52 @@
+use dbus::arg::{ArgType, PropMap, RefArg};
+use serde_json::{Map, Value};
+
+/// Converts an arbitrary D-Bus value into JSON. Dictionaries become objects,
+/// other arrays and structs become arrays and variants are unwrapped.
+pub fn refarg_to_json(value: &dyn RefArg) -> Value {
+    match value.arg_type() {
+        ArgType::Boolean => Value::Bool(value.as_i64() == Some(1)),
+        ArgType::Byte
+        | ArgType::Int16
+        | ArgType::UInt16
+        | ArgType::Int32
+        | ArgType::UInt32
+        | ArgType::Int64 => value.as_i64().map_or(Value::Null, Value::from),
+        ArgType::UInt64 => value.as_u64().map_or(Value::Null, Value::from),
+        ArgType::Double => value.as_f64().map_or(Value::Null, Value::from),
+        ArgType::String | ArgType::ObjectPath | ArgType::Signature => {
+            value.as_str().map_or(Value::Null, Value::from)
+        }
+        ArgType::Variant => match value.as_iter().and_then(|mut inner| inner.next()) {
+            Some(inner) => refarg_to_json(inner),
+            None => Value::Null,
+        },
+        ArgType::Array if value.signature().starts_with("a{") => {
+            let mut object = Map::new();
+            if let Some(mut items) = value.as_iter() {
+                while let (Some(key), Some(item)) = (items.next(), items.next()) {
+                    let key = match key.as_str() {
+                        Some(key) => key.to_string(),
+                        None => refarg_to_json(key).to_string(),
+                    };
+                    object.insert(key, refarg_to_json(item));
+                }
+            }
+            Value::Object(object)
+        }
+        ArgType::Array | ArgType::Struct => match value.as_iter() {
+            Some(items) => Value::Array(items.map(refarg_to_json).collect()),
+            None => Value::Null,
+        },
+        _ => Value::Null,
+    }
+}
+
+pub fn propmap_to_json(properties: &PropMap) -> Value {
+    let object = properties
+        .iter()
+        .map(|(name, value)| (name.clone(), refarg_to_json(&*value.0)))
+        .collect::<Map<String, Value>>();
+
+    Value::Object(object)
+}
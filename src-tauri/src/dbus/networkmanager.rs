@@ -0,0 +1,169 @@
+use anyhow::{Context, Result};
+use dbus::arg::PropMap;
+use dbus::nonblock::{self, SyncConnection};
+use dbus::Path;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::ovpn::networkmanager::{NmConnection, OPENVPN_SERVICE_TYPE};
+
+use super::json::refarg_to_json;
+use super::structs::{Listing, ListingError};
+use super::traced::Traced;
+
+const SERVICE: &str = "org.freedesktop.NetworkManager";
+const SETTINGS_PATH: &str = "/org/freedesktop/NetworkManager/Settings";
+const SETTINGS_INTERFACE: &str = "org.freedesktop.NetworkManager.Settings";
+const CONNECTION_INTERFACE: &str = "org.freedesktop.NetworkManager.Settings.Connection";
+
+type ConnectionSettings = HashMap<String, PropMap>;
+
+#[derive(Serialize, Debug, Clone)]
+pub struct NetworkManagerConnection {
+    pub path: String,
+    pub id: String,
+    pub uuid: String,
+}
+
+/// Read-only access to the connections stored by NetworkManager, used to
+/// import OpenVPN connections set up through it.
+pub struct NetworkManagerDbus {
+    connection: Arc<SyncConnection>,
+}
+
+impl NetworkManagerDbus {
+    pub fn new(connection: Arc<SyncConnection>) -> Self {
+        Self { connection }
+    }
+
+    fn proxy<'a>(&self, path: impl Into<Path<'a>>) -> nonblock::Proxy<'a, Arc<SyncConnection>> {
+        nonblock::Proxy::new(
+            SERVICE,
+            path,
+            Duration::from_secs(5),
+            self.connection.clone(),
+        )
+    }
+
+    async fn get_settings(&self, connection_path: &str) -> Result<ConnectionSettings> {
+        let proxy = self.proxy(Path::new(connection_path).map_err(anyhow::Error::msg)?);
+
+        let (settings,): (ConnectionSettings,) = proxy
+            .method_call(CONNECTION_INTERFACE, "GetSettings", ())
+            .traced("GetSettings", &proxy.path)
+            .await
+            .with_context(|| format!("Failed to fetch settings of {}", connection_path))?;
+
+        Ok(settings)
+    }
+
+    /// Lists the OpenVPN connections. A connection whose settings cannot be
+    /// read is reported in the errors of the listing, whatever its type.
+    pub async fn list_openvpn_connections(&self) -> Result<Listing<NetworkManagerConnection>> {
+        let proxy = self.proxy(SETTINGS_PATH);
+
+        let (paths,): (Vec<Path>,) = proxy
+            .method_call(SETTINGS_INTERFACE, "ListConnections", ())
+            .traced("ListConnections", &proxy.path)
+            .await
+            .with_context(|| "Failed to list NetworkManager connections")?;
+
+        let mut connections = Listing::default();
+        for path in paths.iter() {
+            let settings = match self.get_settings(path).await {
+                Ok(settings) => settings,
+                Err(error) => {
+                    connections.errors.push(ListingError {
+                        path: path.to_string(),
+                        error: format!("{:#}", error),
+                    });
+                    continue;
+                }
+            };
+
+            if setting_str(&settings, "vpn", "service-type").as_deref()
+                != Some(OPENVPN_SERVICE_TYPE)
+            {
+                continue;
+            }
+
+            connections.items.push(NetworkManagerConnection {
+                path: path.to_string(),
+                id: setting_str(&settings, "connection", "id").unwrap_or_default(),
+                uuid: setting_str(&settings, "connection", "uuid").unwrap_or_default(),
+            });
+        }
+
+        Ok(connections)
+    }
+
+    pub async fn get_openvpn_connection(&self, connection_path: &str) -> Result<NmConnection> {
+        let settings = self.get_settings(connection_path).await?;
+        let data = setting_dict(&settings, "vpn", "data");
+        let secrets = secret_names(&settings, &data);
+
+        let never_default = settings
+            .get("ipv4")
+            .and_then(|ipv4| ipv4.get("never-default"))
+            .map(|value| refarg_to_json(&*value.0) == Value::Bool(true))
+            .unwrap_or(false);
+
+        Ok(NmConnection {
+            id: setting_str(&settings, "connection", "id").unwrap_or_default(),
+            service_type: setting_str(&settings, "vpn", "service-type").unwrap_or_default(),
+            data,
+            secrets,
+            never_default,
+        })
+    }
+}
+
+/// The secrets of the connection, named but without their values, which
+/// only a secret agent hands out, possibly after prompting the user. Each
+/// secret has a `<name>-flags` key in `vpn.data`, 4 meaning not required.
+fn secret_names(
+    settings: &ConnectionSettings,
+    data: &HashMap<String, String>,
+) -> HashMap<String, String> {
+    let mut secrets: HashMap<String, String> = setting_dict(settings, "vpn", "secrets")
+        .into_keys()
+        .map(|name| (name, String::new()))
+        .collect();
+
+    for (key, flags) in data.iter() {
+        let required = flags.parse::<u32>().map_or(true, |flags| flags & 4 == 0);
+        if let Some(name) = key.strip_suffix("-flags").filter(|_| required) {
+            secrets.insert(name.to_string(), String::new());
+        }
+    }
+
+    secrets
+}
+
+fn setting_str(settings: &ConnectionSettings, group: &str, key: &str) -> Option<String> {
+    settings
+        .get(group)?
+        .get(key)?
+        .0
+        .as_str()
+        .map(str::to_string)
+}
+
+/// Reads an `a{ss}` setting such as `vpn.data`.
+fn setting_dict(settings: &ConnectionSettings, group: &str, key: &str) -> HashMap<String, String> {
+    let value = match settings.get(group).and_then(|group| group.get(key)) {
+        Some(value) => refarg_to_json(&*value.0),
+        None => return HashMap::new(),
+    };
+
+    match value {
+        Value::Object(object) => object
+            .into_iter()
+            .filter_map(|(key, value)| value.as_str().map(|value| (key, value.to_string())))
+            .collect(),
+        _ => HashMap::new(),
+    }
+}
@@ -1,25 +1,55 @@
 use anyhow::{Context, Result};
-use dbus::arg::RefArg;
+use dbus::arg::{PropMap, RefArg, Variant};
 use dbus::message::MatchRule;
 use dbus::nonblock::stdintf::org_freedesktop_dbus::Properties;
 use dbus::nonblock::{self, SyncConnection};
 use dbus::Path;
 use dbus_tokio::connection;
 use futures::Future;
+use std::collections::HashMap;
 use std::pin::Pin;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 use std::time::Duration;
 use tokio::sync::{broadcast, Mutex};
 use tokio_stream::StreamExt;
 
-use crate::structs::ImportConfigPayload;
+use crate::ovpn::{self, remotes::RemoteServer};
+use crate::structs::LogMessage;
 use crate::utils;
 
-use super::structs::{OpenVPN3Config, OpenVPN3Session};
+use super::codes::{STATUS_MAJOR_SESSION, STATUS_MINOR_SESS_AUTH_URL, STATUS_MINOR_SESS_REMOVED};
+use super::overrides::{self, ConfigOverride, OverrideValue};
+use super::structs::{Listing, ListingError, OpenVPN3Config, OpenVPN3Session, SignalScope};
+use super::traced::Traced;
+
+#[derive(Debug, Clone)]
+struct KnownSession {
+    config_path: Option<String>,
+    config_name: Option<String>,
+    owner: Option<u32>,
+    started_by_konewka: bool,
+}
+
+/// Sessions seen on the bus, None for those that could not be looked up,
+/// until they are removed.
+type KnownSessions = Arc<std::sync::Mutex<HashMap<String, Option<KnownSession>>>>;
+
+type DbusProxy = nonblock::Proxy<'static, Arc<SyncConnection>>;
+
+/// The overrides that point a configuration at one of its remotes.
+const REMOTE_OVERRIDES: [&str; 3] = ["server-override", "port-override", "proto-override"];
+
+/// How many objects have their properties fetched at the same time when
+/// listing configs or sessions.
+const MAX_CONCURRENT_FETCHES: usize = 8;
 
 pub struct OpenVPN3Dbus {
     connection: Arc<SyncConnection>,
-    log_sender: broadcast::Sender<(String, String, u32, u32, String)>,
+    log_sender: broadcast::Sender<LogMessage>,
+    known_sessions: KnownSessions,
+    signal_scope: Arc<RwLock<SignalScope>>,
+    /// Proxies by service and object path
+    proxies: std::sync::Mutex<HashMap<(String, String), DbusProxy>>,
 }
 
 impl OpenVPN3Dbus {
@@ -31,31 +61,46 @@ impl OpenVPN3Dbus {
             panic!("Lost connection to D-Bus: {}", err);
         });
 
-        let (tx_log, _) = broadcast::channel::<(String, String, u32, u32, String)>(16);
+        let (tx_log, _) = broadcast::channel::<LogMessage>(16);
 
         Ok(Self {
             connection: conn,
             log_sender: tx_log,
+            known_sessions: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            signal_scope: Arc::new(RwLock::new(SignalScope::default())),
+            proxies: std::sync::Mutex::new(HashMap::new()),
         })
     }
 
     pub fn on_log<F>(&self, callback: F)
     where
-        F: Fn(String, String, u32, u32, String) + Send + 'static,
+        F: Fn(LogMessage) + Send + 'static,
     {
         let mut rx = self.log_sender.subscribe();
         let cb = Arc::new(Mutex::new(callback));
 
         tokio::spawn(async move {
-            while let Ok((path, member, group, level, message)) = rx.recv().await {
-                cb.lock().await(path, member, group, level, message);
+            while let Ok(log) = rx.recv().await {
+                cb.lock().await(log);
             }
         });
     }
 
+    pub fn set_signal_scope(&self, scope: SignalScope) {
+        *self.signal_scope.write().unwrap() = scope;
+    }
+
+    /// The system bus connection, for talking to other services on it.
+    pub fn connection(&self) -> Arc<SyncConnection> {
+        self.connection.clone()
+    }
+
     pub async fn signals(&self) -> Result<(), anyhow::Error> {
         let conn = self.connection.clone();
         let tx = self.log_sender.clone();
+        let known_sessions = self.known_sessions.clone();
+        let signal_scope = self.signal_scope.clone();
+        let uid = utils::current_uid();
 
         tokio::spawn(async move {
             let match_rule = MatchRule::new()
@@ -64,43 +109,95 @@ impl OpenVPN3Dbus {
 
             let signal_match = conn.add_match(match_rule).await.unwrap();
 
-            println!("Listening for signals...");
+            tracing::info!("Listening for signals...");
 
-            let (_incoming_signal, mut stream): (
+            let (_incoming_signal, stream): (
                 dbus::nonblock::MsgMatch,
                 futures_channel::mpsc::UnboundedReceiver<(_, (u32, u32, String))>,
             ) = signal_match.stream();
 
-            while let Some(v) = stream.next().await {
-                let message = v.0;
+            // Looking up a session seen for the first time takes a D-Bus
+            // round trip, several are done at once so that one slow lookup
+            // does not hold up the other signals, which stay in order
+            let lookup_conn = conn.clone();
+            let lookup_sessions = known_sessions.clone();
+            let mut signals = Box::pin(futures::StreamExt::buffered(
+                stream.map(
+                    move |(message, codes): (dbus::Message, (u32, u32, String))| {
+                        let conn = lookup_conn.clone();
+                        let known_sessions = lookup_sessions.clone();
+                        async move {
+                            let path = message.path().unwrap().into_static();
+                            let session = lookup_session(&conn, &known_sessions, &path).await;
+                            (message, path, codes, session)
+                        }
+                    },
+                ),
+                MAX_CONCURRENT_FETCHES,
+            ));
+
+            while let Some((message, path, codes, session)) = signals.next().await {
                 let member = message.member().unwrap().into_static();
                 let member = member.as_str().unwrap().to_string();
-
-                let path = message.path().unwrap().into_static();
-
-                let (first_code, second_code, message) = v.1;
-                let payload = (
-                    path.to_string(),
-                    member.clone(),
-                    first_code,
-                    second_code,
-                    message.clone(),
-                );
-
-                let cmember = member.clone();
-                if cmember == "StatusChange" && first_code == 3 && second_code == 22 {
-                    match open::that(message) {
-                        Ok(_) => (),
-                        Err(_) => {
-                            println!("Failed to open link");
+                let (first_code, second_code, message) = codes;
+
+                // Only sessions of this user get a browser opened, whatever
+                // is forwarded to the UI
+                let own_session = session.as_ref().is_some_and(|session| {
+                    session.started_by_konewka || (uid.is_some() && session.owner == uid)
+                });
+
+                if member == "StatusChange"
+                    && first_code == STATUS_MAJOR_SESSION
+                    && second_code == STATUS_MINOR_SESS_AUTH_URL
+                {
+                    if own_session {
+                        if let Err(error) = open::that(&message) {
+                            tracing::warn!(%error, "Failed to open link");
                         }
+                    } else {
+                        tracing::debug!(path = %path, "Not opening the link of another user");
                     }
                 }
 
+                if member == "StatusChange" && second_code == STATUS_MINOR_SESS_REMOVED {
+                    known_sessions.lock().unwrap().remove(&path.to_string());
+                }
+
+                let scope = *signal_scope.read().unwrap();
+                let in_scope = match scope {
+                    SignalScope::All => true,
+                    SignalScope::User => own_session,
+                    SignalScope::Konewka => session
+                        .as_ref()
+                        .is_some_and(|session| session.started_by_konewka),
+                };
+
+                if !in_scope {
+                    tracing::trace!(path = %path, ?scope, "Ignoring signal outside of scope");
+                    continue;
+                }
+
+                let session = session.unwrap_or(KnownSession {
+                    config_path: None,
+                    config_name: None,
+                    owner: None,
+                    started_by_konewka: false,
+                });
+                let payload = LogMessage {
+                    path: path.to_string(),
+                    member,
+                    first_flag: first_code,
+                    second_flag: second_code,
+                    message,
+                    config_path: session.config_path,
+                    config_name: session.config_name,
+                };
+
                 match tx.send(payload) {
                     Ok(_) => (),
                     Err(_) => {
-                        println!("Failed to send signal");
+                        tracing::debug!("Failed to send signal, no subscribers");
                     }
                 }
             }
@@ -110,74 +207,255 @@ impl OpenVPN3Dbus {
     }
 
     pub async fn remove_config(&self, config_path: String) -> Result<(), anyhow::Error> {
-        let conn = self.connection.clone();
-
-        let proxy = nonblock::Proxy::new(
-            "net.openvpn.v3.configuration",
-            dbus::Path::new(config_path).unwrap(),
-            Duration::from_secs(5),
-            conn,
-        );
+        let proxy = self.proxy("net.openvpn.v3.configuration", &config_path)?;
 
         proxy
             .method_call("net.openvpn.v3.configuration", "Remove", ())
+            .traced("Remove", &proxy.path)
             .await
             .with_context(|| "Failed to remove config")?;
 
         Ok(())
     }
 
-    pub async fn new_tunnel(&self, config_path: String) -> Result<String, anyhow::Error> {
-        let conn = self.connection.clone();
+    pub async fn rename_config(&self, config_path: &str, name: &str) -> Result<(), anyhow::Error> {
+        let proxy = self.proxy("net.openvpn.v3.configuration", config_path)?;
 
-        let proxy = nonblock::Proxy::new(
-            "net.openvpn.v3.sessions",
-            "/net/openvpn/v3/sessions",
-            Duration::from_secs(5),
-            conn,
-        );
+        proxy
+            .set("net.openvpn.v3.configuration", "name", name.to_string())
+            .traced("Set name", &proxy.path)
+            .await
+            .with_context(|| "Failed to rename config")?;
+
+        Ok(())
+    }
+
+    /// Lets the local user `uid` use the configuration.
+    pub async fn grant_access(&self, config_path: &str, uid: u32) -> Result<(), anyhow::Error> {
+        let proxy = self.proxy("net.openvpn.v3.configuration", config_path)?;
+
+        proxy
+            .method_call("net.openvpn.v3.configuration", "AccessGrant", (uid,))
+            .traced("AccessGrant", &proxy.path)
+            .await
+            .with_context(|| format!("Failed to grant access to UID {}", uid))?;
+
+        Ok(())
+    }
+
+    pub async fn revoke_access(&self, config_path: &str, uid: u32) -> Result<(), anyhow::Error> {
+        let proxy = self.proxy("net.openvpn.v3.configuration", config_path)?;
+
+        proxy
+            .method_call("net.openvpn.v3.configuration", "AccessRevoke", (uid,))
+            .traced("AccessRevoke", &proxy.path)
+            .await
+            .with_context(|| format!("Failed to revoke access of UID {}", uid))?;
+
+        Ok(())
+    }
+
+    /// Sets one of the writable boolean properties of a configuration,
+    /// e.g. `public_access` or `locked_down`.
+    pub async fn set_config_flag(
+        &self,
+        config_path: &str,
+        name: &str,
+        value: bool,
+    ) -> Result<(), anyhow::Error> {
+        let proxy = self.proxy("net.openvpn.v3.configuration", config_path)?;
+
+        proxy
+            .set("net.openvpn.v3.configuration", name, value)
+            .traced("Set config flag", &proxy.path)
+            .await
+            .with_context(|| format!("Failed to set {}", name))?;
+
+        Ok(())
+    }
+
+    /// Makes the configuration read-only for good, not even the owner can
+    /// change it afterwards.
+    pub async fn seal_config(&self, config_path: &str) -> Result<(), anyhow::Error> {
+        let proxy = self.proxy("net.openvpn.v3.configuration", config_path)?;
+
+        proxy
+            .method_call("net.openvpn.v3.configuration", "Seal", ())
+            .traced("Seal", &proxy.path)
+            .await
+            .with_context(|| "Failed to seal config")?;
+
+        Ok(())
+    }
+
+    pub async fn get_overrides(
+        &self,
+        config_path: &str,
+    ) -> Result<Vec<ConfigOverride>, anyhow::Error> {
+        let proxy = self.proxy("net.openvpn.v3.configuration", config_path)?;
+
+        let properties: PropMap = proxy
+            .get("net.openvpn.v3.configuration", "overrides")
+            .traced("Get overrides", &proxy.path)
+            .await
+            .with_context(|| "Failed to fetch config overrides")?;
+
+        Ok(overrides::from_properties(&properties))
+    }
+
+    /// Sets an override after checking its value, see `overrides::validate`.
+    pub async fn set_override(
+        &self,
+        config_path: &str,
+        name: &str,
+        value: &OverrideValue,
+    ) -> Result<(), anyhow::Error> {
+        let value = overrides::validate(name, value)?;
+        let proxy = self.proxy("net.openvpn.v3.configuration", config_path)?;
+
+        proxy
+            .method_call(
+                "net.openvpn.v3.configuration",
+                "SetOverride",
+                (name, Variant(value.to_refarg())),
+            )
+            .traced("SetOverride", &proxy.path)
+            .await
+            .with_context(|| format!("Failed to set override {}", name))?;
+
+        Ok(())
+    }
+
+    pub async fn unset_override(&self, config_path: &str, name: &str) -> Result<(), anyhow::Error> {
+        let proxy = self.proxy("net.openvpn.v3.configuration", config_path)?;
+
+        proxy
+            .method_call("net.openvpn.v3.configuration", "UnsetOverride", (name,))
+            .traced("UnsetOverride", &proxy.path)
+            .await
+            .with_context(|| format!("Failed to unset override {}", name))?;
+
+        Ok(())
+    }
+
+    /// Makes the next connections of a configuration go to `remote` through
+    /// the server, port and protocol overrides, or lets openvpn3 pick one of
+    /// the remotes of the configuration again.
+    pub async fn select_remote(
+        &self,
+        config_path: &str,
+        remote: Option<&RemoteServer>,
+    ) -> Result<(), anyhow::Error> {
+        match remote {
+            Some(remote) => {
+                for (name, value) in [
+                    ("server-override", remote.host.clone()),
+                    ("port-override", remote.port.to_string()),
+                    ("proto-override", remote.proto.clone()),
+                ] {
+                    self.set_override(config_path, name, &OverrideValue::String(value))
+                        .await?;
+                }
+            }
+            None => {
+                // Unsetting an override that is not set is an error
+                for set in self.get_overrides(config_path).await? {
+                    if REMOTE_OVERRIDES.contains(&set.name.as_str()) {
+                        self.unset_override(config_path, &set.name).await?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The remotes of a configuration, as listed in its content.
+    pub async fn get_remotes(&self, config_path: &str) -> Result<Vec<RemoteServer>, anyhow::Error> {
+        let content = self.fetch_config(config_path).await?;
+        let (config, _) = ovpn::parser::parse(&content);
+        Ok(ovpn::remotes::list(&config))
+    }
+
+    /// Returns the configuration as .ovpn text, with everything it needs
+    /// inlined. Only the owner of the configuration may fetch it.
+    pub async fn fetch_config(&self, config_path: &str) -> Result<String, anyhow::Error> {
+        let proxy = self.proxy("net.openvpn.v3.configuration", config_path)?;
+
+        let (content,): (String,) = proxy
+            .method_call("net.openvpn.v3.configuration", "Fetch", ())
+            .traced("Fetch", &proxy.path)
+            .await
+            .with_context(|| format!("Failed to fetch config {}", config_path))?;
+
+        Ok(content)
+    }
+
+    pub async fn new_tunnel(&self, config_path: String) -> Result<String, anyhow::Error> {
+        let proxy = self.proxy("net.openvpn.v3.sessions", "/net/openvpn/v3/sessions")?;
 
         let (session_path,): (Path,) = proxy
             .method_call(
                 "net.openvpn.v3.sessions",
                 "NewTunnel",
-                (dbus::Path::new(config_path).unwrap(),),
+                (Path::new(config_path.clone()).map_err(anyhow::Error::msg)?,),
             )
+            .traced("NewTunnel", &proxy.path)
             .await
             .with_context(|| "Failed to create new tunnel")?;
 
-        let session_conn = self.connection.clone();
-        let proxy_session = nonblock::Proxy::new(
-            "net.openvpn.v3.sessions",
-            session_path.clone(),
-            Duration::from_secs(5),
-            session_conn,
+        // Recorded before anything else is awaited, the first signals of the
+        // session are on their way already
+        self.known_sessions.lock().unwrap().insert(
+            session_path.to_string(),
+            Some(KnownSession {
+                config_path: Some(config_path.clone()),
+                config_name: None,
+                owner: utils::current_uid(),
+                started_by_konewka: true,
+            }),
         );
 
+        if let Ok(config) = self.get_config(&config_path).await {
+            if let Some(session) = self
+                .known_sessions
+                .lock()
+                .unwrap()
+                .get_mut(&session_path.to_string())
+                .and_then(Option::as_mut)
+            {
+                session.config_name = Some(config.name);
+            }
+        }
+
+        let proxy_session = self.proxy("net.openvpn.v3.sessions", &session_path)?;
+
         let proxy = proxy_session.clone();
         let closure = move || {
             let proxy = proxy.clone();
             Box::pin(async move {
                 let result: Result<(), dbus::Error> = proxy
                     .method_call("net.openvpn.v3.sessions", "Ready", ())
+                    .traced("Ready", &proxy.path)
                     .await;
                 return result;
             }) as Pin<Box<dyn Future<Output = Result<_, dbus::Error>> + Send>>
         };
 
         if let Ok(_) = utils::async_retry(closure, 5).await {
-            println!("Tunnel is ready");
+            tracing::info!(session_path = %session_path, "Tunnel is ready");
         } else {
             return Err(anyhow::anyhow!("Failed to create tunnel"));
         }
 
         if let Ok(()) = proxy_session
             .method_call("net.openvpn.v3.sessions", "LogForward", (true,))
+            .traced("LogForward", &proxy_session.path)
             .await
         {
-            println!("LogForwarded");
+            tracing::debug!(session_path = %session_path, "Log forwarding enabled");
         } else {
-            println!("Failed to forward logs");
+            tracing::warn!(session_path = %session_path, "Failed to forward logs");
         }
 
         let session_path_as_string = session_path.to_string();
@@ -185,86 +463,103 @@ impl OpenVPN3Dbus {
         Ok(session_path_as_string)
     }
 
-    async fn fetch_config_data(&self, config_path: &str) -> Result<(String, u32), anyhow::Error> {
-        let conn = self.connection.clone();
+    pub async fn get_log_verbosity(&self, session_path: String) -> Result<u32, anyhow::Error> {
+        let proxy = self.proxy("net.openvpn.v3.sessions", &session_path)?;
 
-        let proxy = nonblock::Proxy::new(
-            "net.openvpn.v3.configuration",
-            dbus::Path::new(config_path).unwrap(),
-            Duration::from_secs(5),
-            conn,
-        );
+        let log_verbosity: u32 = proxy
+            .get("net.openvpn.v3.sessions", "log_verbosity")
+            .traced("Get log_verbosity", &proxy.path)
+            .await
+            .with_context(|| "Failed to fetch log verbosity")?;
+
+        Ok(log_verbosity)
+    }
+
+    pub async fn set_log_verbosity(
+        &self,
+        session_path: String,
+        log_verbosity: u32,
+    ) -> Result<(), anyhow::Error> {
+        let proxy = self.proxy("net.openvpn.v3.sessions", &session_path)?;
 
-        let config_name: String = proxy
-            .get("net.openvpn.v3.configuration", "name")
+        proxy
+            .set("net.openvpn.v3.sessions", "log_verbosity", log_verbosity)
+            .traced("Set log_verbosity", &proxy.path)
             .await
-            .with_context(|| "Failed to fetch config data")?;
+            .with_context(|| "Failed to set log verbosity")?;
 
-        let used_count: u32 = proxy
-            .get("net.openvpn.v3.configuration", "used_count")
+        Ok(())
+    }
+
+    pub async fn set_log_forward(
+        &self,
+        session_path: String,
+        enabled: bool,
+    ) -> Result<(), anyhow::Error> {
+        let proxy = self.proxy("net.openvpn.v3.sessions", &session_path)?;
+
+        proxy
+            .method_call("net.openvpn.v3.sessions", "LogForward", (enabled,))
+            .traced("LogForward", &proxy.path)
             .await
-            .with_context(|| "Failed to fetch config data")?;
+            .with_context(|| "Failed to change log forwarding")?;
 
-        Ok((config_name, used_count))
+        Ok(())
     }
 
-    pub async fn get_configs(&self) -> Result<Vec<OpenVPN3Config>, anyhow::Error> {
-        let conn = self.connection.clone();
+    pub async fn get_configs(&self) -> Result<Listing<OpenVPN3Config>, anyhow::Error> {
+        let configs_paths = self.get_config_paths().await?;
 
-        let proxy = nonblock::Proxy::new(
+        Ok(self
+            .fetch_all(
+                "net.openvpn.v3.configuration",
+                &configs_paths,
+                |path, properties| Ok(OpenVPN3Config::from_properties(path, properties)),
+            )
+            .await)
+    }
+
+    pub async fn get_config_paths(&self) -> Result<Vec<Path<'static>>, anyhow::Error> {
+        let proxy = self.proxy(
             "net.openvpn.v3.configuration",
             "/net/openvpn/v3/configuration",
-            Duration::from_secs(5),
-            conn,
-        );
+        )?;
 
-        let (configs_paths,): (Vec<Path>,) = proxy
+        let (configs_paths,): (Vec<Path<'static>>,) = proxy
             .method_call("net.openvpn.v3.configuration", "FetchAvailableConfigs", ())
+            .traced("FetchAvailableConfigs", &proxy.path)
             .await
             .with_context(|| "Failed to fetch available configs")?;
 
-        let mut configs = vec![];
-        for config in configs_paths.iter() {
-            let config_data = self.fetch_config_data(config).await?;
+        self.prune_proxies("net.openvpn.v3.configuration", &configs_paths);
 
-            configs.push(OpenVPN3Config {
-                path: config.to_string(),
-                name: config_data.0,
-                used_count: config_data.1,
-            })
-        }
+        Ok(configs_paths)
+    }
 
-        Ok(configs)
+    pub async fn get_config(&self, config_path: &str) -> Result<OpenVPN3Config, anyhow::Error> {
+        let properties = self.get_config_properties(config_path).await?;
+        Ok(OpenVPN3Config::from_properties(config_path, &properties))
     }
 
     pub async fn import_config(
         &self,
-        payload: ImportConfigPayload,
+        config_name: String,
+        config_content: String,
+        single_use: bool,
+        persistent: bool,
     ) -> Result<String, anyhow::Error> {
-        let conn = self.connection.clone();
-
-        let proxy = nonblock::Proxy::new(
+        let proxy = self.proxy(
             "net.openvpn.v3.configuration",
             "/net/openvpn/v3/configuration",
-            Duration::from_secs(5),
-            conn,
-        );
-
-        // read config file from path
-        let config_content =
-            std::fs::read_to_string(&payload.config_file).with_context(|| "Failed to read file")?;
+        )?;
 
         let (config_path,): (dbus::Path,) = proxy
             .method_call(
                 "net.openvpn.v3.configuration",
                 "Import",
-                (
-                    payload.config_name,
-                    config_content,
-                    payload.single_use,
-                    payload.persistent,
-                ),
+                (config_name, config_content, single_use, persistent),
             )
+            .traced("Import", &proxy.path)
             .await
             .with_context(|| "Failed to import config")?;
 
@@ -273,90 +568,42 @@ impl OpenVPN3Dbus {
         Ok(as_string)
     }
 
-    pub async fn get_sessions(&self) -> Result<Vec<OpenVPN3Session>, anyhow::Error> {
-        let conn = self.connection.clone();
-
-        let proxy = nonblock::Proxy::new(
-            "net.openvpn.v3.sessions",
-            "/net/openvpn/v3/sessions",
-            Duration::from_secs(5),
-            conn,
-        );
-
-        let (sessions,): (Vec<Path>,) = proxy
-            .method_call("net.openvpn.v3.sessions", "FetchAvailableSessions", ())
-            .await
-            .with_context(|| "Failed to fetch available sessions")?;
-
-        let mut sessions_with_data: Vec<OpenVPN3Session> = vec![];
+    pub async fn get_sessions(&self) -> Result<Listing<OpenVPN3Session>, anyhow::Error> {
+        let sessions = self.get_session_paths().await?;
 
-        for session in sessions.iter() {
-            let session_conn = self.connection.clone();
-            let session_proxy = nonblock::Proxy::new(
+        Ok(self
+            .fetch_all(
                 "net.openvpn.v3.sessions",
-                session.clone(),
-                Duration::from_secs(5),
-                session_conn,
-            );
-
-            let (major_code, minor_code, status_message): (u32, u32, String) = session_proxy
-                .get("net.openvpn.v3.sessions", "status")
-                .await
-                .with_context(|| "Failed to fetch session data")?;
-
-            let session_created: u64 = session_proxy
-                .get("net.openvpn.v3.sessions", "session_created")
-                .await
-                .with_context(|| "Failed to fetch session data")?;
-
-            let session = OpenVPN3Session {
-                path: session.to_string(),
-                major_code,
-                minor_code,
-                status_message,
-                session_created,
-            };
-
-            sessions_with_data.push(session);
-        }
-
-        Ok(sessions_with_data)
+                &sessions,
+                OpenVPN3Session::from_properties,
+            )
+            .await)
     }
 
-    pub async fn has_session(&self) -> Result<bool, anyhow::Error> {
-        let conn = self.connection.clone();
-
-        let proxy = nonblock::Proxy::new(
-            "net.openvpn.v3.sessions",
-            "/net/openvpn/v3/sessions",
-            Duration::from_secs(5),
-            conn,
-        );
+    pub async fn get_session_paths(&self) -> Result<Vec<Path<'static>>, anyhow::Error> {
+        let proxy = self.proxy("net.openvpn.v3.sessions", "/net/openvpn/v3/sessions")?;
 
-        let (sessions,): (Vec<Path>,) = proxy
+        let (sessions,): (Vec<Path<'static>>,) = proxy
             .method_call("net.openvpn.v3.sessions", "FetchAvailableSessions", ())
+            .traced("FetchAvailableSessions", &proxy.path)
             .await
             .with_context(|| "Failed to fetch available sessions")?;
 
-        if sessions.is_empty() {
-            return Ok(false);
-        }
+        self.prune_proxies("net.openvpn.v3.sessions", &sessions);
 
-        Ok(true)
+        Ok(sessions)
     }
 
-    pub async fn disconnect_session(&self, session_path: String) -> Result<(), anyhow::Error> {
-        let conn = self.connection.clone();
+    pub async fn has_session(&self) -> Result<bool, anyhow::Error> {
+        Ok(!self.get_session_paths().await?.is_empty())
+    }
 
-        let proxy = nonblock::Proxy::new(
-            "net.openvpn.v3.sessions",
-            dbus::Path::new(session_path).unwrap(),
-            Duration::from_secs(5),
-            conn,
-        );
+    pub async fn disconnect_session(&self, session_path: String) -> Result<(), anyhow::Error> {
+        let proxy = self.proxy("net.openvpn.v3.sessions", &session_path)?;
 
         proxy
             .method_call("net.openvpn.v3.sessions", "Disconnect", ())
+            .traced("Disconnect", &proxy.path)
             .await
             .with_context(|| "Failed to disconnect session")?;
 
@@ -364,30 +611,234 @@ impl OpenVPN3Dbus {
     }
 
     pub async fn connect_session(&self, session_path: String) -> Result<(), anyhow::Error> {
-        let conn = self.connection.clone();
-
-        let proxy = nonblock::Proxy::new(
-            "net.openvpn.v3.sessions",
-            dbus::Path::new(session_path).unwrap(),
-            Duration::from_secs(5),
-            conn,
-        );
+        let proxy = self.proxy("net.openvpn.v3.sessions", &session_path)?;
 
         proxy
             .method_call("net.openvpn.v3.sessions", "Connect", ())
+            .traced("Connect", &proxy.path)
             .await
             .with_context(|| "Failed to connect session")?;
 
         Ok(())
     }
 
+    /// Returns a proxy for `object_path` of `service`, reusing the one made
+    /// by an earlier call.
+    fn proxy(&self, service: &str, object_path: &str) -> Result<DbusProxy, anyhow::Error> {
+        let key = (service.to_string(), object_path.to_string());
+        let mut proxies = self.proxies.lock().unwrap();
+
+        if let Some(proxy) = proxies.get(&key) {
+            return Ok(proxy.clone());
+        }
+
+        let proxy = nonblock::Proxy::new(
+            service.to_string(),
+            dbus::Path::new(object_path.to_string()).map_err(anyhow::Error::msg)?,
+            Duration::from_secs(5),
+            self.connection.clone(),
+        );
+        proxies.insert(key, proxy.clone());
+
+        Ok(proxy)
+    }
+
+    /// Forgets the proxies of `service` objects that are no longer listed.
+    fn prune_proxies(&self, service: &str, listed: &[Path]) {
+        let root = format!("/{}", service.replace('.', "/"));
+
+        self.proxies
+            .lock()
+            .unwrap()
+            .retain(|(proxy_service, path), _| {
+                proxy_service != service
+                    || *path == root
+                    || listed.iter().any(|listed| &**listed == path)
+            });
+    }
+
+    async fn fetch_properties(
+        &self,
+        interface: &str,
+        object_path: &str,
+    ) -> Result<PropMap, anyhow::Error> {
+        let proxy = self.proxy(interface, object_path)?;
+
+        let properties = proxy
+            .get_all(interface)
+            .traced("GetAll", &proxy.path)
+            .await
+            .with_context(|| format!("Failed to fetch properties of {}", object_path))?;
+
+        Ok(properties)
+    }
+
+    /// Fetches the properties of `paths` concurrently, a few objects at a
+    /// time, and builds an item out of each.
+    async fn fetch_all<T, F>(
+        &self,
+        interface: &str,
+        paths: &[Path<'static>],
+        build: F,
+    ) -> Listing<T>
+    where
+        F: Fn(&str, &PropMap) -> Result<T, anyhow::Error>,
+    {
+        use futures::stream::{self, StreamExt as _};
+
+        // Owned paths keep the futures free of borrowed lifetimes, which
+        // would stop callers from boxing them as Send
+        let paths: Vec<String> = paths.iter().map(|path| path.to_string()).collect();
+        let fetches = paths.into_iter().map(|path| async move {
+            let properties = self.fetch_properties(interface, &path).await;
+            (path, properties)
+        });
+
+        // tokio_stream::StreamExt has a collect of its own
+        let results: Vec<_> =
+            futures::StreamExt::collect(stream::iter(fetches).buffered(MAX_CONCURRENT_FETCHES))
+                .await;
+
+        let mut listing = Listing::default();
+        for (path, result) in results {
+            match result.and_then(|properties| build(&path, &properties)) {
+                Ok(item) => listing.items.push(item),
+                Err(error) => {
+                    tracing::warn!("Skipping {}: {:#}", path, error);
+                    listing.errors.push(ListingError {
+                        path,
+                        error: format!("{:#}", error),
+                    });
+                }
+            }
+        }
+
+        listing
+    }
+
+    pub async fn get_config_properties(&self, config_path: &str) -> Result<PropMap, anyhow::Error> {
+        self.fetch_properties("net.openvpn.v3.configuration", config_path)
+            .await
+    }
+
+    pub async fn get_session_properties(
+        &self,
+        session_path: &str,
+    ) -> Result<PropMap, anyhow::Error> {
+        self.fetch_properties("net.openvpn.v3.sessions", session_path)
+            .await
+    }
+
+    pub async fn get_netcfg_properties(&self) -> Result<PropMap, anyhow::Error> {
+        self.fetch_properties("net.openvpn.v3.netcfg", "/net/openvpn/v3/netcfg")
+            .await
+    }
+
+    pub async fn get_netcfg_interfaces(&self) -> Result<Vec<(String, PropMap)>, anyhow::Error> {
+        let proxy = self.proxy("net.openvpn.v3.netcfg", "/net/openvpn/v3/netcfg")?;
+
+        let (interfaces,): (Vec<Path>,) = proxy
+            .method_call("net.openvpn.v3.netcfg", "FetchInterfaceList", ())
+            .traced("FetchInterfaceList", &proxy.path)
+            .await
+            .with_context(|| "Failed to fetch network interfaces")?;
+
+        let mut interfaces_with_data = vec![];
+        for interface in interfaces.iter() {
+            let properties = self
+                .fetch_properties("net.openvpn.v3.netcfg", interface)
+                .await?;
+
+            interfaces_with_data.push((interface.to_string(), properties));
+        }
+
+        Ok(interfaces_with_data)
+    }
+
+    /// Reads the `version` property of an openvpn3 service, e.g.
+    /// `net.openvpn.v3.sessions` served at `/net/openvpn/v3/sessions`.
+    pub async fn get_service_version(&self, service: &str) -> Result<String, anyhow::Error> {
+        let proxy = self.proxy(service, &format!("/{}", service.replace('.', "/")))?;
+
+        let version: String = proxy
+            .get(service, "version")
+            .traced("Get version", &proxy.path)
+            .await
+            .with_context(|| format!("Failed to fetch version of {}", service))?;
+
+        Ok(version)
+    }
+
     pub async fn disconnect_all(&self) -> Result<(), anyhow::Error> {
-        let sessions = self.get_sessions().await?;
+        let sessions = self.get_session_paths().await?;
 
         for session in sessions.iter() {
-            self.disconnect_session(session.path.clone()).await?;
+            self.disconnect_session(session.to_string()).await?;
         }
 
         Ok(())
     }
 }
+
+/// Returns what is known about the session behind a backend signal, asking
+/// the session manager the first time a session path is seen. A failed
+/// lookup is remembered as well, so that the signals of a session that is
+/// gone or belongs to someone else do not each cost a round trip.
+async fn lookup_session(
+    conn: &Arc<SyncConnection>,
+    known_sessions: &KnownSessions,
+    session_path: &Path<'_>,
+) -> Option<KnownSession> {
+    if let Some(session) = known_sessions
+        .lock()
+        .unwrap()
+        .get(&session_path.to_string())
+    {
+        return session.clone();
+    }
+
+    let proxy = nonblock::Proxy::new(
+        "net.openvpn.v3.sessions",
+        session_path.clone(),
+        Duration::from_secs(5),
+        conn.clone(),
+    );
+
+    let properties = match proxy
+        .get_all("net.openvpn.v3.sessions")
+        .traced("GetAll", &proxy.path)
+        .await
+    {
+        Ok(properties) => properties,
+        Err(_) => {
+            return known_sessions
+                .lock()
+                .unwrap()
+                .entry(session_path.to_string())
+                .or_insert(None)
+                .clone()
+        }
+    };
+
+    let session = KnownSession {
+        config_path: properties
+            .get("config_path")
+            .and_then(|value| value.0.as_str())
+            .map(|value| value.to_string()),
+        config_name: properties
+            .get("config_name")
+            .and_then(|value| value.0.as_str())
+            .map(|value| value.to_string()),
+        owner: properties
+            .get("owner")
+            .and_then(|value| value.0.as_u64())
+            .map(|value| value as u32),
+        started_by_konewka: false,
+    };
+
+    let mut known_sessions = known_sessions.lock().unwrap();
+    known_sessions
+        .entry(session_path.to_string())
+        .or_insert(Some(session))
+        .clone()
+}
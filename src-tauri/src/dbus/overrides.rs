@@ -0,0 +1,309 @@
+use anyhow::{anyhow, Result};
+use dbus::arg::{ArgType, PropMap, RefArg};
+use serde::{Deserialize, Serialize};
+
+/// How the value of an override is typed and validated.
+#[derive(Serialize, Debug, Clone, Copy)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OverrideKind {
+    Bool,
+    /// A host name or IP address
+    Host,
+    Port,
+    Choice {
+        options: &'static [&'static str],
+    },
+}
+
+#[derive(Serialize, Debug, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub struct OverrideDefinition {
+    pub name: &'static str,
+    pub kind: OverrideKind,
+    pub description: &'static str,
+}
+
+/// The overrides openvpn3 accepts through `SetOverride`.
+pub const OVERRIDES: [OverrideDefinition; 17] = [
+    OverrideDefinition {
+        name: "server-override",
+        kind: OverrideKind::Host,
+        description: "Connect to this server instead of the remotes of the configuration",
+    },
+    OverrideDefinition {
+        name: "port-override",
+        kind: OverrideKind::Port,
+        description: "Connect to this port instead of the one of the configuration",
+    },
+    OverrideDefinition {
+        name: "proto-override",
+        kind: OverrideKind::Choice {
+            options: &["udp", "tcp"],
+        },
+        description: "Transport protocol to use",
+    },
+    OverrideDefinition {
+        name: "ipv6",
+        kind: OverrideKind::Choice {
+            options: &["yes", "no", "default"],
+        },
+        description: "Whether IPv6 is used inside the tunnel",
+    },
+    OverrideDefinition {
+        name: "dns-fallback-google",
+        kind: OverrideKind::Bool,
+        description: "Use Google DNS servers when the server pushes none",
+    },
+    OverrideDefinition {
+        name: "dns-setup-disabled",
+        kind: OverrideKind::Bool,
+        description: "Ignore DNS settings pushed by the server",
+    },
+    OverrideDefinition {
+        name: "dns-scope",
+        kind: OverrideKind::Choice {
+            options: &["global", "tunnel"],
+        },
+        description: "Which DNS queries are sent to the VPN servers",
+    },
+    OverrideDefinition {
+        name: "dns-sync-lookup",
+        kind: OverrideKind::Bool,
+        description: "Resolve the server name synchronously",
+    },
+    OverrideDefinition {
+        name: "allow-compression",
+        kind: OverrideKind::Choice {
+            options: &["no", "asym", "yes"],
+        },
+        description: "Whether data channel compression is allowed",
+    },
+    OverrideDefinition {
+        name: "enable-legacy-algorithms",
+        kind: OverrideKind::Bool,
+        description: "Allow ciphers such as BF-CBC",
+    },
+    OverrideDefinition {
+        name: "tls-version-min",
+        kind: OverrideKind::Choice {
+            options: &[
+                "disabled", "default", "tls_1_0", "tls_1_1", "tls_1_2", "tls_1_3",
+            ],
+        },
+        description: "Lowest TLS version accepted",
+    },
+    OverrideDefinition {
+        name: "tls-cert-profile",
+        kind: OverrideKind::Choice {
+            options: &["insecure", "legacy", "preferred", "suiteb"],
+        },
+        description: "Restrictions on certificate algorithms and key sizes",
+    },
+    OverrideDefinition {
+        name: "proxy-host",
+        kind: OverrideKind::Host,
+        description: "HTTP proxy to connect through",
+    },
+    OverrideDefinition {
+        name: "proxy-port",
+        kind: OverrideKind::Port,
+        description: "Port of the HTTP proxy",
+    },
+    OverrideDefinition {
+        name: "proxy-auth-cleartext",
+        kind: OverrideKind::Bool,
+        description: "Allow sending proxy credentials unencrypted",
+    },
+    OverrideDefinition {
+        name: "persist-tun",
+        kind: OverrideKind::Bool,
+        description: "Keep the tun device while reconnecting",
+    },
+    OverrideDefinition {
+        name: "auth-fail-retry",
+        kind: OverrideKind::Bool,
+        description: "Ask for credentials again after a failed authentication",
+    },
+];
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum OverrideValue {
+    Bool(bool),
+    String(String),
+}
+
+impl OverrideValue {
+    pub fn to_refarg(&self) -> Box<dyn RefArg> {
+        match self {
+            OverrideValue::Bool(value) => Box::new(*value),
+            OverrideValue::String(value) => Box::new(value.clone()),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ConfigOverride {
+    pub name: String,
+    pub value: OverrideValue,
+}
+
+pub fn definition(name: &str) -> Result<&'static OverrideDefinition> {
+    OVERRIDES
+        .iter()
+        .find(|definition| definition.name == name)
+        .ok_or_else(|| anyhow!("{} is not a known override", name))
+}
+
+/// Checks `value` against the type of the override and returns it in the
+/// form openvpn3 expects.
+pub fn validate(name: &str, value: &OverrideValue) -> Result<OverrideValue> {
+    let definition = definition(name)?;
+
+    match (definition.kind, value) {
+        (OverrideKind::Bool, OverrideValue::Bool(_)) => Ok(value.clone()),
+        (OverrideKind::Bool, OverrideValue::String(_)) => {
+            Err(anyhow!("{} must be true or false", name))
+        }
+        (_, OverrideValue::Bool(_)) => Err(anyhow!("{} must be a string", name)),
+        (OverrideKind::Host, OverrideValue::String(host)) => {
+            let host = host.trim();
+            if host.is_empty() || host.contains(char::is_whitespace) {
+                return Err(anyhow!("{} is not a valid host for {}", host, name));
+            }
+            Ok(OverrideValue::String(host.to_string()))
+        }
+        (OverrideKind::Port, OverrideValue::String(port)) => match port.trim().parse::<u16>() {
+            Ok(port) if port > 0 => Ok(OverrideValue::String(port.to_string())),
+            _ => Err(anyhow!("{} is not a valid port for {}", port, name)),
+        },
+        (OverrideKind::Choice { options }, OverrideValue::String(choice)) => {
+            if options.contains(&choice.as_str()) {
+                Ok(value.clone())
+            } else {
+                Err(anyhow!(
+                    "{} must be one of {}, not {}",
+                    name,
+                    options.join(", "),
+                    choice
+                ))
+            }
+        }
+    }
+}
+
+/// Reads the `overrides` property of a configuration. Overrides set by
+/// other tools that Konewka does not know about are listed as well.
+pub fn from_properties(overrides: &PropMap) -> Vec<ConfigOverride> {
+    let mut overrides: Vec<ConfigOverride> = overrides
+        .iter()
+        .map(|(name, value)| {
+            let value = match value.0.arg_type() {
+                ArgType::Boolean => OverrideValue::Bool(value.0.as_i64() == Some(1)),
+                _ => match value.0.as_str() {
+                    Some(value) => OverrideValue::String(value.to_string()),
+                    None => OverrideValue::String(format!("{:?}", value.0)),
+                },
+            };
+
+            ConfigOverride {
+                name: name.clone(),
+                value,
+            }
+        })
+        .collect();
+
+    overrides.sort_by(|a, b| a.name.cmp(&b.name));
+    overrides
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use dbus::arg::Variant;
+
+    fn string(value: &str) -> OverrideValue {
+        OverrideValue::String(value.to_string())
+    }
+
+    #[test]
+    fn validates_bool_values() {
+        let name = "dns-setup-disabled";
+
+        assert_eq!(
+            validate(name, &OverrideValue::Bool(true)).unwrap(),
+            OverrideValue::Bool(true)
+        );
+        assert!(validate(name, &string("true")).is_err());
+        assert!(validate("server-override", &OverrideValue::Bool(true)).is_err());
+    }
+
+    #[test]
+    fn validates_host_values() {
+        let name = "server-override";
+
+        assert_eq!(
+            validate(name, &string(" vpn.example.com ")).unwrap(),
+            string("vpn.example.com")
+        );
+        assert_eq!(validate(name, &string("::1")).unwrap(), string("::1"));
+        assert!(validate(name, &string("")).is_err());
+        assert!(validate(name, &string("vpn example.com")).is_err());
+    }
+
+    #[test]
+    fn validates_port_values() {
+        let name = "port-override";
+
+        assert_eq!(validate(name, &string(" 443 ")).unwrap(), string("443"));
+        assert_eq!(validate(name, &string("65535")).unwrap(), string("65535"));
+        assert!(validate(name, &string("0")).is_err());
+        assert!(validate(name, &string("65536")).is_err());
+        assert!(validate(name, &string("https")).is_err());
+        assert!(validate(name, &string("")).is_err());
+    }
+
+    #[test]
+    fn validates_choice_values() {
+        let name = "proto-override";
+
+        assert_eq!(validate(name, &string("tcp")).unwrap(), string("tcp"));
+        assert!(validate(name, &string("TCP")).is_err());
+        assert!(validate(name, &string("sctp")).is_err());
+    }
+
+    #[test]
+    fn refuses_unknown_overrides() {
+        assert!(definition("no-such-override").is_err());
+        assert!(validate("no-such-override", &string("yes")).is_err());
+        assert!(validate("no-such-override", &OverrideValue::Bool(true)).is_err());
+    }
+
+    #[test]
+    fn reads_overrides_from_properties() {
+        let mut properties = PropMap::new();
+        properties.insert(
+            "port-override".to_string(),
+            Variant(Box::new("443".to_string())),
+        );
+        properties.insert("dns-setup-disabled".to_string(), Variant(Box::new(true)));
+        properties.insert("persist-tun".to_string(), Variant(Box::new(false)));
+        properties.insert("set-by-another-tool".to_string(), Variant(Box::new(7u32)));
+
+        let overrides: Vec<(String, OverrideValue)> = from_properties(&properties)
+            .into_iter()
+            .map(|item| (item.name, item.value))
+            .collect();
+
+        assert_eq!(
+            overrides,
+            vec![
+                ("dns-setup-disabled".to_string(), OverrideValue::Bool(true)),
+                ("persist-tun".to_string(), OverrideValue::Bool(false)),
+                ("port-override".to_string(), string("443")),
+                ("set-by-another-tool".to_string(), string("7")),
+            ]
+        );
+    }
+}
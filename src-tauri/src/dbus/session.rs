@@ -8,6 +8,6 @@ impl Session {
     }
 
     pub fn forward_logs(&self) {
-        println!("Forwarding logs for session: {}", self.path);
+        tracing::debug!(session_path = %self.path, "Forwarding logs");
     }
 }
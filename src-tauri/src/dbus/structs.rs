@@ -1,3 +1,5 @@
+use anyhow::{anyhow, Result};
+use dbus::arg::{PropMap, RefArg};
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -5,6 +7,63 @@ pub struct OpenVPN3Config {
     pub path: String,
     pub name: String,
     pub used_count: u32,
+    /// The remaining properties are missing when the installed openvpn3
+    /// does not expose them yet
+    pub import_timestamp: Option<u64>,
+    pub last_used_timestamp: Option<u64>,
+    pub persistent: Option<bool>,
+    pub single_use: Option<bool>,
+    pub locked_down: Option<bool>,
+    pub readonly: Option<bool>,
+    pub valid: Option<bool>,
+    pub owner: Option<u32>,
+    pub dco: Option<bool>,
+    pub transfer_owner_session: Option<bool>,
+    pub public_access: Option<bool>,
+    /// UIDs of the users the configuration was granted to
+    pub acl: Option<Vec<u32>>,
+    pub tags: Option<Vec<String>>,
+}
+
+impl OpenVPN3Config {
+    /// Builds the config from the result of a `GetAll` call on its object.
+    pub fn from_properties(path: &str, properties: &PropMap) -> Self {
+        let prop = |name: &str| properties.get(name).map(|value| &value.0);
+        let prop_u64 = |name: &str| prop(name).and_then(|value| value.as_u64());
+        let prop_bool = |name: &str| {
+            prop(name)
+                .and_then(|value| value.as_i64())
+                .map(|value| value == 1)
+        };
+
+        Self {
+            path: path.to_string(),
+            name: prop("name")
+                .and_then(|value| value.as_str())
+                .unwrap_or_default()
+                .to_string(),
+            used_count: prop_u64("used_count").unwrap_or_default() as u32,
+            import_timestamp: prop_u64("import_timestamp"),
+            last_used_timestamp: prop_u64("last_used_timestamp"),
+            persistent: prop_bool("persistent"),
+            single_use: prop_bool("single_use"),
+            locked_down: prop_bool("locked_down"),
+            readonly: prop_bool("readonly"),
+            valid: prop_bool("valid"),
+            owner: prop_u64("owner").map(|owner| owner as u32),
+            dco: prop_bool("dco"),
+            transfer_owner_session: prop_bool("transfer_owner_session"),
+            public_access: prop_bool("public_access"),
+            acl: prop("acl").and_then(|value| value.as_iter()).map(|uids| {
+                uids.filter_map(|uid| uid.as_u64().map(|uid| uid as u32))
+                    .collect()
+            }),
+            tags: prop("tags").and_then(|value| value.as_iter()).map(|tags| {
+                tags.filter_map(|tag| tag.as_str().map(str::to_string))
+                    .collect()
+            }),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -14,4 +73,79 @@ pub struct OpenVPN3Session {
     pub minor_code: u32,
     pub status_message: String,
     pub session_created: u64,
+    /// The configuration the session was started from
+    pub config_path: Option<String>,
+}
+
+impl OpenVPN3Session {
+    /// Builds the session from the result of a `GetAll` call on its object.
+    pub fn from_properties(path: &str, properties: &PropMap) -> Result<Self> {
+        let prop = |name: &str| {
+            properties
+                .get(name)
+                .map(|value| &value.0)
+                .ok_or_else(|| anyhow!("{} has no {} property", path, name))
+        };
+
+        let mut status = prop("status")?
+            .as_iter()
+            .ok_or_else(|| anyhow!("{} has an invalid status", path))?;
+        let (major_code, minor_code, status_message) =
+            match (status.next(), status.next(), status.next()) {
+                (Some(major), Some(minor), Some(message)) => (
+                    major.as_u64().unwrap_or_default() as u32,
+                    minor.as_u64().unwrap_or_default() as u32,
+                    message.as_str().unwrap_or_default().to_string(),
+                ),
+                _ => return Err(anyhow!("{} has an invalid status", path)),
+            };
+
+        Ok(Self {
+            path: path.to_string(),
+            major_code,
+            minor_code,
+            status_message,
+            session_created: prop("session_created")?.as_u64().unwrap_or_default(),
+            config_path: prop("config_path")
+                .ok()
+                .and_then(|value| value.as_str())
+                .map(|value| value.to_string()),
+        })
+    }
+}
+
+/// The objects of a listing whose properties could be read, and an error
+/// for each of the others, so that one broken object does not hide the rest.
+#[derive(Serialize, Debug)]
+pub struct Listing<T> {
+    pub items: Vec<T>,
+    pub errors: Vec<ListingError>,
+}
+
+impl<T> Default for Listing<T> {
+    fn default() -> Self {
+        Self {
+            items: vec![],
+            errors: vec![],
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ListingError {
+    pub path: String,
+    pub error: String,
+}
+
+/// Which sessions' backend signals are forwarded to the UI.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SignalScope {
+    /// Sessions started from Konewka since it was launched
+    Konewka,
+    /// Sessions owned by the current user
+    #[default]
+    User,
+    /// Every session visible on the system bus
+    All,
 }
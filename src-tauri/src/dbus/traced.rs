@@ -0,0 +1,45 @@
+use std::pin::Pin;
+use std::time::Instant;
+
+use futures::Future;
+use tracing::Instrument;
+
+pub trait Traced<T> {
+    /// Runs a D-Bus call inside a `dbus_call` span and logs how long it took
+    /// and whether it succeeded.
+    fn traced<'a>(
+        self,
+        method: &'a str,
+        path: &'a str,
+    ) -> Pin<Box<dyn Future<Output = Result<T, dbus::Error>> + Send + 'a>>;
+}
+
+impl<T, F> Traced<T> for F
+where
+    T: Send + 'static,
+    F: Future<Output = Result<T, dbus::Error>> + Send + 'static,
+{
+    fn traced<'a>(
+        self,
+        method: &'a str,
+        path: &'a str,
+    ) -> Pin<Box<dyn Future<Output = Result<T, dbus::Error>> + Send + 'a>> {
+        let span = tracing::debug_span!("dbus_call", method, path);
+
+        Box::pin(
+            async move {
+                let started = Instant::now();
+                let result = self.await;
+                let duration_ms = started.elapsed().as_millis() as u64;
+
+                match &result {
+                    Ok(_) => tracing::debug!(duration_ms, "D-Bus call succeeded"),
+                    Err(error) => tracing::warn!(duration_ms, %error, "D-Bus call failed"),
+                }
+
+                result
+            }
+            .instrument(span),
+        )
+    }
+}
@@ -0,0 +1,261 @@
+use std::fs::File;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use flate2::{write::GzEncoder, Compression};
+use serde_json::{json, Value};
+
+use crate::dbus::json::propmap_to_json;
+use crate::dbus::openvpn3::OpenVPN3Dbus;
+use crate::logs::export::{write_records, LogExportFormat};
+use crate::logs::history::LogHistory;
+use crate::settings::SettingsStore;
+
+const OPENVPN3_SERVICES: [&str; 4] = [
+    "net.openvpn.v3.configuration",
+    "net.openvpn.v3.sessions",
+    "net.openvpn.v3.log",
+    "net.openvpn.v3.netcfg",
+];
+
+/// Keys whose values identify the user or their server outright, e.g. the
+/// Access Server a profile is refreshed from.
+const SENSITIVE_KEYS: [&str; 2] = ["url", "username"];
+
+/// Writes a gzipped tarball describing the current state of Konewka and the
+/// openvpn3 services. Everything is gathered locally; a failing query is
+/// recorded in the bundle instead of aborting it. Everything but the
+/// version information goes through `redact`.
+pub async fn write_bundle(
+    openvpn3: &OpenVPN3Dbus,
+    log_history: &LogHistory,
+    settings: &SettingsStore,
+    file: &Path,
+) -> Result<()> {
+    let mut entries: Vec<(&str, Vec<u8>)> = vec![];
+
+    entries.push(("konewka.json", to_json_bytes(&konewka_info(log_history))?));
+    let settings =
+        serde_json::to_value(settings.get()).with_context(|| "Failed to serialize settings")?;
+    entries.push(("settings.json", redacted_json(settings)?));
+    entries.push(("versions.json", to_json_bytes(&versions(openvpn3).await)?));
+    entries.push(("configs.json", redacted_json(configs(openvpn3).await)?));
+    entries.push(("sessions.json", redacted_json(sessions(openvpn3).await)?));
+    entries.push(("netcfg.json", redacted_json(netcfg(openvpn3).await)?));
+    entries.push((
+        "resolv.conf",
+        redact(&read_or_error("/etc/resolv.conf")).into_bytes(),
+    ));
+    entries.push(("logs.txt", redacted_logs(log_history)?));
+
+    let file = File::create(file).with_context(|| "Failed to create diagnostics bundle")?;
+    let mut archive = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+    let mtime = chrono::Utc::now().timestamp() as u64;
+
+    for (name, data) in entries {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(mtime);
+
+        archive
+            .append_data(&mut header, format!("konewka-diagnostics/{}", name), &*data)
+            .with_context(|| format!("Failed to add {} to diagnostics bundle", name))?;
+    }
+
+    archive
+        .into_inner()
+        .and_then(|encoder| encoder.finish())
+        .with_context(|| "Failed to write diagnostics bundle")?;
+
+    Ok(())
+}
+
+/// Masks URLs (web-auth links carry session tokens), e-mail addresses and
+/// IPv4 addresses in a log message.
+pub fn redact(message: &str) -> String {
+    message
+        .split_inclusive(char::is_whitespace)
+        .map(|piece| {
+            let word = piece.trim_end();
+            format!("{}{}", redact_word(word), &piece[word.len()..])
+        })
+        .collect()
+}
+
+/// Runs every string of `value` through `redact` and masks the values of
+/// `SENSITIVE_KEYS` entirely.
+fn redact_value(value: &mut Value) {
+    match value {
+        Value::String(text) => *text = redact(text),
+        Value::Array(items) => items.iter_mut().for_each(redact_value),
+        Value::Object(map) => {
+            for (key, item) in map.iter_mut() {
+                if SENSITIVE_KEYS.contains(&key.as_str()) {
+                    *item = Value::from("<redacted>");
+                } else {
+                    redact_value(item);
+                }
+            }
+        }
+        _ => (),
+    }
+}
+
+fn redact_word(word: &str) -> String {
+    if let Some(scheme_end) = word.find("://") {
+        let rest = &word[scheme_end + 3..];
+        let host_end = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+        let host = redact_ipv4(&rest[..host_end]);
+
+        if host_end == rest.len() {
+            return format!("{}://{}", &word[..scheme_end], host);
+        }
+        return format!("{}://{}/<redacted>", &word[..scheme_end], host);
+    }
+
+    if let Some(at) = word.find('@') {
+        if at > 0 && word[at..].contains('.') {
+            return "<email>".to_string();
+        }
+    }
+
+    redact_ipv4(word)
+}
+
+fn redact_ipv4(text: &str) -> String {
+    let mut redacted = String::new();
+    let mut run = String::new();
+
+    for c in text.chars().chain(std::iter::once(' ')) {
+        if c.is_ascii_digit() || c == '.' {
+            run.push(c);
+            continue;
+        }
+
+        let trimmed = run.trim_end_matches('.');
+        if trimmed.parse::<std::net::Ipv4Addr>().is_ok() {
+            redacted.push_str("<ip>");
+            redacted.push_str(&run[trimmed.len()..]);
+        } else {
+            redacted.push_str(&run);
+        }
+        run.clear();
+        redacted.push(c);
+    }
+
+    redacted.pop();
+    redacted
+}
+
+fn konewka_info(log_history: &LogHistory) -> Value {
+    json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "generated_at": chrono::Utc::now().to_rfc3339(),
+        "log_records": log_history.query(None, None, None).len(),
+        "os_release": read_or_error("/etc/os-release"),
+    })
+}
+
+async fn versions(openvpn3: &OpenVPN3Dbus) -> Value {
+    let mut versions = serde_json::Map::new();
+
+    for service in OPENVPN3_SERVICES {
+        let version = match openvpn3.get_service_version(service).await {
+            Ok(version) => Value::from(version),
+            Err(error) => error_value(error),
+        };
+        versions.insert(service.to_string(), version);
+    }
+
+    Value::Object(versions)
+}
+
+async fn configs(openvpn3: &OpenVPN3Dbus) -> Value {
+    let configs = match openvpn3.get_config_paths().await {
+        Ok(configs) => configs,
+        Err(error) => return error_value(error),
+    };
+
+    let mut configs_with_properties = vec![];
+    for config in configs.iter() {
+        let properties = match openvpn3.get_config_properties(config).await {
+            Ok(properties) => propmap_to_json(&properties),
+            Err(error) => error_value(error),
+        };
+        configs_with_properties
+            .push(json!({ "path": config.to_string(), "properties": properties }));
+    }
+
+    Value::Array(configs_with_properties)
+}
+
+async fn sessions(openvpn3: &OpenVPN3Dbus) -> Value {
+    let sessions = match openvpn3.get_session_paths().await {
+        Ok(sessions) => sessions,
+        Err(error) => return error_value(error),
+    };
+
+    let mut sessions_with_properties = vec![];
+    for session in sessions.iter() {
+        let properties = match openvpn3.get_session_properties(session).await {
+            Ok(properties) => propmap_to_json(&properties),
+            Err(error) => error_value(error),
+        };
+        sessions_with_properties
+            .push(json!({ "path": session.to_string(), "properties": properties }));
+    }
+
+    Value::Array(sessions_with_properties)
+}
+
+async fn netcfg(openvpn3: &OpenVPN3Dbus) -> Value {
+    let properties = match openvpn3.get_netcfg_properties().await {
+        Ok(properties) => propmap_to_json(&properties),
+        Err(error) => error_value(error),
+    };
+
+    let interfaces = match openvpn3.get_netcfg_interfaces().await {
+        Ok(interfaces) => interfaces
+            .iter()
+            .map(|(path, properties)| {
+                json!({ "path": path, "properties": propmap_to_json(properties) })
+            })
+            .collect(),
+        Err(error) => error_value(error),
+    };
+
+    json!({ "properties": properties, "interfaces": interfaces })
+}
+
+fn redacted_logs(log_history: &LogHistory) -> Result<Vec<u8>> {
+    let mut records = log_history.query(None, None, None);
+    for record in records.iter_mut() {
+        record.log.message = redact(&record.log.message);
+    }
+
+    let mut logs = vec![];
+    write_records(&mut logs, &records, LogExportFormat::Text)?;
+
+    Ok(logs)
+}
+
+fn redacted_json(mut value: Value) -> Result<Vec<u8>> {
+    redact_value(&mut value);
+    to_json_bytes(&value)
+}
+
+fn to_json_bytes(value: &Value) -> Result<Vec<u8>> {
+    serde_json::to_vec_pretty(value).with_context(|| "Failed to serialize diagnostics")
+}
+
+fn error_value(error: anyhow::Error) -> Value {
+    json!({ "error": format!("{:#}", error) })
+}
+
+fn read_or_error(path: &str) -> String {
+    match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(error) => format!("Failed to read {}: {}\n", path, error),
+    }
+}
@@ -0,0 +1,4 @@
+pub mod directory;
+pub mod replace;
+pub mod server;
+pub mod watch;
@@ -0,0 +1,344 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::dbus::openvpn3::OpenVPN3Dbus;
+use crate::ovpn::prepare;
+use crate::settings::{ConfigSource, SettingsStore};
+
+const CONFIG_EXTENSIONS: [&str; 2] = ["ovpn", "conf"];
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum CandidateStatus {
+    /// Will be imported
+    New,
+    /// A configuration imported from identical content still exists
+    AlreadyImported {
+        config: String,
+    },
+    /// Another file of the same scan has identical content
+    Duplicate {
+        of: String,
+    },
+    Invalid {
+        errors: Vec<String>,
+    },
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportCandidate {
+    pub file: String,
+    pub config_name: String,
+    pub hash: String,
+    pub warnings: usize,
+    pub status: CandidateStatus,
+    /// Configuration content with referenced files inlined
+    #[serde(skip)]
+    pub content: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "result", rename_all = "snake_case")]
+pub enum ImportResult {
+    Imported { config: String },
+    Skipped { reason: String },
+    Failed { error: String },
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportOutcome {
+    pub file: String,
+    pub config_name: String,
+    #[serde(flatten)]
+    pub result: ImportResult,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct DirectoryImportReport {
+    pub imported: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    pub files: Vec<ImportOutcome>,
+}
+
+pub fn content_hash(content: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(content))
+}
+
+/// Remembers the hash of the content handed to openvpn3 for `config_path`,
+/// so that directory imports recognise configurations imported any other
+/// way.
+pub fn record_content_hash(settings: &SettingsStore, config_path: &str, content: &str) {
+    let hash = content_hash(content.as_bytes());
+    if let Err(error) =
+        settings.update_config(config_path, |config| config.content_hash = Some(hash))
+    {
+        tracing::warn!(
+            "Failed to record the content hash of {}: {:#}",
+            config_path,
+            error
+        );
+    }
+}
+
+/// Lists the .ovpn and .conf files of `dir`, descending into
+/// subdirectories when `recursive` is set.
+pub fn scan(dir: &Path, recursive: bool) -> Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    scan_into(dir, recursive, &mut files)?;
+    files.sort();
+    Ok(files)
+}
+
+fn scan_into(dir: &Path, recursive: bool, files: &mut Vec<PathBuf>) -> Result<()> {
+    let entries =
+        std::fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))?;
+
+    for entry in entries {
+        let entry = entry?;
+        let path = entry.path();
+
+        // Symlinked directories are not followed, a link pointing back up
+        // the tree would recurse forever
+        if entry.file_type()?.is_dir() {
+            if recursive {
+                scan_into(&path, recursive, files)?;
+            }
+        } else if is_config_file(&path) {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+pub fn is_config_file(path: &Path) -> bool {
+    path.extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .is_some_and(|extension| CONFIG_EXTENSIONS.contains(&extension.as_str()))
+}
+
+/// Config paths by content hash, limited to configurations openvpn3 still
+/// knows about. Both the hash of the imported file and the hash of the
+/// content handed to openvpn3 are included.
+pub async fn imported_hashes(
+    openvpn3: &OpenVPN3Dbus,
+    settings: &SettingsStore,
+) -> Result<HashMap<String, String>> {
+    let existing: Vec<String> = openvpn3
+        .get_config_paths()
+        .await?
+        .iter()
+        .map(|path| path.to_string())
+        .collect();
+
+    Ok(settings
+        .get()
+        .configs
+        .into_iter()
+        .filter(|(path, _)| existing.contains(path))
+        .flat_map(|(path, config)| {
+            config
+                .source
+                .map(|source| source.hash)
+                .into_iter()
+                .chain(config.content_hash)
+                .map(move |hash| (hash, path.clone()))
+        })
+        .collect())
+}
+
+/// Works out what importing `files` would do without importing anything.
+/// Names come from the file names, falling back to the path relative to
+/// `base_dir` when two files share a name.
+pub async fn preview(
+    openvpn3: &OpenVPN3Dbus,
+    settings: &SettingsStore,
+    base_dir: &Path,
+    files: &[PathBuf],
+) -> Result<Vec<ImportCandidate>> {
+    let imported = imported_hashes(openvpn3, settings).await?;
+    let mut seen: HashMap<String, String> = HashMap::new();
+    let mut candidates = vec![];
+
+    let stems: Vec<String> = files.iter().map(|file| file_stem(file)).collect();
+
+    for (file, stem) in files.iter().zip(stems.iter()) {
+        let display = file.display().to_string();
+        let config_name = if stems.iter().filter(|other| *other == stem).count() > 1 {
+            relative_name(base_dir, file)
+        } else {
+            stem.clone()
+        };
+
+        let read = std::fs::read(file)
+            .map_err(anyhow::Error::from)
+            .and_then(|raw| {
+                let prepared = prepare::prepare_file(file)?;
+                Ok((raw, prepared))
+            });
+
+        let (raw, prepared) = match read {
+            Ok(read) => read,
+            Err(error) => {
+                candidates.push(ImportCandidate {
+                    file: display,
+                    config_name,
+                    hash: String::new(),
+                    warnings: 0,
+                    status: CandidateStatus::Invalid {
+                        errors: vec![format!("{:#}", error)],
+                    },
+                    content: String::new(),
+                });
+                continue;
+            }
+        };
+
+        let hash = content_hash(&raw);
+        let warnings = prepared.report.diagnostics.len() - prepared.report.errors().count();
+
+        let imported_as = imported
+            .get(&hash)
+            .or_else(|| imported.get(&content_hash(prepared.content.as_bytes())));
+
+        let status = if let Some(config) = imported_as {
+            CandidateStatus::AlreadyImported {
+                config: config.clone(),
+            }
+        } else if let Some(of) = seen.get(&hash) {
+            CandidateStatus::Duplicate { of: of.clone() }
+        } else if !prepared.report.valid {
+            CandidateStatus::Invalid {
+                errors: prepared
+                    .report
+                    .errors()
+                    .map(|error| error.to_string())
+                    .collect(),
+            }
+        } else {
+            CandidateStatus::New
+        };
+
+        seen.entry(hash.clone()).or_insert_with(|| display.clone());
+
+        candidates.push(ImportCandidate {
+            file: display,
+            config_name,
+            hash,
+            warnings,
+            status,
+            content: prepared.content,
+        });
+    }
+
+    Ok(candidates)
+}
+
+/// Imports the candidates marked as new and records where each came from,
+/// so that a later scan recognises them.
+pub async fn import(
+    openvpn3: &OpenVPN3Dbus,
+    settings: &SettingsStore,
+    candidates: Vec<ImportCandidate>,
+    single_use: bool,
+    persistent: bool,
+) -> DirectoryImportReport {
+    let mut report = DirectoryImportReport::default();
+
+    for candidate in candidates {
+        let result = match candidate.status {
+            CandidateStatus::New => {
+                let imported_hash = content_hash(candidate.content.as_bytes());
+                match openvpn3
+                    .import_config(
+                        candidate.config_name.clone(),
+                        candidate.content,
+                        single_use,
+                        persistent,
+                    )
+                    .await
+                {
+                    Ok(config_path) => {
+                        let source = ConfigSource {
+                            file: candidate.file.clone(),
+                            hash: candidate.hash,
+                        };
+                        if let Err(error) = settings.update_config(&config_path, |config| {
+                            config.source = Some(source);
+                            config.content_hash = Some(imported_hash);
+                        }) {
+                            tracing::warn!(
+                                "Failed to record source of {}: {:#}",
+                                config_path,
+                                error
+                            );
+                        }
+                        ImportResult::Imported {
+                            config: config_path,
+                        }
+                    }
+                    Err(error) => {
+                        tracing::error!("Failed to import {}: {:#}", candidate.file, error);
+                        ImportResult::Failed {
+                            error: format!("{:#}", error),
+                        }
+                    }
+                }
+            }
+            CandidateStatus::AlreadyImported { config } => ImportResult::Skipped {
+                reason: format!("Already imported as {}", config),
+            },
+            CandidateStatus::Duplicate { of } => ImportResult::Skipped {
+                reason: format!("Same content as {}", of),
+            },
+            CandidateStatus::Invalid { errors } => ImportResult::Failed {
+                error: errors.join("\n"),
+            },
+        };
+
+        match result {
+            ImportResult::Imported { .. } => report.imported += 1,
+            ImportResult::Skipped { .. } => report.skipped += 1,
+            ImportResult::Failed { .. } => report.failed += 1,
+        }
+
+        report.files.push(ImportOutcome {
+            file: candidate.file,
+            config_name: candidate.config_name,
+            result,
+        });
+    }
+
+    tracing::info!(
+        "Directory import finished: {} imported, {} skipped, {} failed",
+        report.imported,
+        report.skipped,
+        report.failed
+    );
+
+    report
+}
+
+fn file_stem(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+fn relative_name(base_dir: &Path, file: &Path) -> String {
+    let relative = file.strip_prefix(base_dir).unwrap_or(file);
+    relative
+        .with_extension("")
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().to_string())
+        .collect::<Vec<_>>()
+        .join(" - ")
+}
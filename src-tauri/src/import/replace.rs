@@ -0,0 +1,101 @@
+use anyhow::{anyhow, Context, Result};
+
+use crate::dbus::openvpn3::OpenVPN3Dbus;
+use crate::import::directory;
+use crate::settings::{ConfigSettings, SettingsStore};
+
+/// openvpn3 cannot change the content of an imported configuration, so an
+/// updated profile is imported as a new configuration named `name` and only
+/// then is `config_path` removed. Its flags, access grants, settings and
+/// openvpn3 overrides carry over to the new path, the settings adjusted by
+/// `update`. A sealed configuration is refused, it was sealed so that its
+/// content stays as it is. Returns the path of the new configuration.
+pub async fn replace_config<F>(
+    openvpn3: &OpenVPN3Dbus,
+    settings: &SettingsStore,
+    config_path: &str,
+    name: &str,
+    content: String,
+    update: F,
+) -> Result<String>
+where
+    F: FnOnce(&mut ConfigSettings),
+{
+    let original = openvpn3
+        .get_config(config_path)
+        .await
+        .with_context(|| format!("Failed to read {}", config_path))?;
+
+    if original.readonly == Some(true) {
+        return Err(anyhow!(
+            "{} is sealed and cannot be replaced, remove it and import the profile again",
+            original.name
+        ));
+    }
+
+    let overrides = openvpn3
+        .get_overrides(config_path)
+        .await
+        .with_context(|| format!("Failed to read the overrides of {}", config_path))?;
+
+    let hash = directory::content_hash(content.as_bytes());
+    let new_path = openvpn3
+        .import_config(
+            name.to_string(),
+            content,
+            original.single_use.unwrap_or(false),
+            original.persistent.unwrap_or(true),
+        )
+        .await?;
+
+    for item in overrides.iter() {
+        if let Err(error) = openvpn3
+            .set_override(&new_path, &item.name, &item.value)
+            .await
+        {
+            tracing::warn!(
+                "Failed to carry override {} over to {}: {:#}",
+                item.name,
+                new_path,
+                error
+            );
+        }
+    }
+
+    for uid in original.acl.iter().flatten() {
+        if let Err(error) = openvpn3.grant_access(&new_path, *uid).await {
+            tracing::warn!(
+                "Failed to carry access of UID {} over to {}: {:#}",
+                uid,
+                new_path,
+                error
+            );
+        }
+    }
+
+    for (flag, value) in [
+        ("public_access", original.public_access),
+        ("locked_down", original.locked_down),
+    ] {
+        if value != Some(true) {
+            continue;
+        }
+        if let Err(error) = openvpn3.set_config_flag(&new_path, flag, true).await {
+            tracing::warn!("Failed to carry {} over to {}: {:#}", flag, new_path, error);
+        }
+    }
+
+    settings.update(|settings| {
+        settings.move_config(config_path, &new_path);
+        let config = settings.configs.entry(new_path.clone()).or_default();
+        config.content_hash = Some(hash);
+        update(config);
+    })?;
+
+    openvpn3
+        .remove_config(config_path.to_string())
+        .await
+        .with_context(|| format!("Failed to remove the previous version {}", config_path))?;
+
+    Ok(new_path)
+}
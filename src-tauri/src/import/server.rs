@@ -0,0 +1,360 @@
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// The kinds of connection profile an Access Server hands out through its
+/// REST API.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ProfileType {
+    /// Locked to the user, who authenticates again when connecting
+    Userlogin,
+    /// Connects without asking for credentials, if the user is allowed to
+    Autologin,
+    /// Not tied to a user, credentials are asked for when connecting
+    Generic,
+}
+
+impl ProfileType {
+    pub const ALL: [ProfileType; 3] = [
+        ProfileType::Userlogin,
+        ProfileType::Autologin,
+        ProfileType::Generic,
+    ];
+
+    fn endpoint(&self) -> &'static str {
+        match self {
+            ProfileType::Userlogin => "GetUserlogin",
+            ProfileType::Autologin => "GetAutologin",
+            ProfileType::Generic => "GetGeneric",
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileTypeAvailability {
+    pub profile_type: ProfileType,
+    pub available: bool,
+    pub error: Option<String>,
+}
+
+/// A client for an Access Server compatible portal. Plain http:// URLs
+/// are only accepted for servers on this machine, such as a local stand-in
+/// used for testing.
+pub struct ProfileServer {
+    client: reqwest::Client,
+    base_url: String,
+    username: String,
+    password: String,
+}
+
+impl ProfileServer {
+    pub fn new(url: &str, username: &str, password: &str) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .with_context(|| "Failed to create HTTP client")?;
+
+        Ok(Self {
+            client,
+            base_url: normalize_url(url)?,
+            username: username.to_string(),
+            password: password.to_string(),
+        })
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Downloads a profile of the given type.
+    pub async fn download(&self, profile_type: ProfileType) -> Result<String> {
+        let url = format!("{}/rest/{}", self.base_url, profile_type.endpoint());
+
+        let response = self
+            .client
+            .get(&url)
+            .basic_auth(&self.username, Some(&self.password))
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach {}", self.base_url))?;
+
+        let status = response.status();
+        let body = response
+            .text()
+            .await
+            .with_context(|| format!("Failed to read the response of {}", url))?;
+
+        if status == reqwest::StatusCode::UNAUTHORIZED {
+            return Err(anyhow!("The server rejected the username or password"));
+        }
+
+        // Access Server reports errors as an XML document, sometimes with a
+        // successful status code
+        if let Some(synopsis) = error_synopsis(&body) {
+            return Err(anyhow!("{}", synopsis));
+        }
+
+        if !status.is_success() {
+            return Err(anyhow!("{} returned {}", url, status));
+        }
+
+        if !body.contains("remote") {
+            return Err(anyhow!("{} did not return an OpenVPN profile", url));
+        }
+
+        Ok(body)
+    }
+
+    /// Asks for every profile type with a HEAD request and reports which
+    /// ones the server provides to this user, without downloading them.
+    pub async fn available_profile_types(&self) -> Vec<ProfileTypeAvailability> {
+        let mut availability = vec![];
+
+        for profile_type in ProfileType::ALL {
+            let result = self.probe(profile_type).await;
+            availability.push(ProfileTypeAvailability {
+                profile_type,
+                available: result.is_ok(),
+                error: result.err().map(|error| format!("{:#}", error)),
+            });
+        }
+
+        availability
+    }
+
+    async fn probe(&self, profile_type: ProfileType) -> Result<()> {
+        let url = format!("{}/rest/{}", self.base_url, profile_type.endpoint());
+
+        let response = self
+            .client
+            .head(&url)
+            .basic_auth(&self.username, Some(&self.password))
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach {}", self.base_url))?;
+
+        let status = response.status();
+        if status == reqwest::StatusCode::UNAUTHORIZED {
+            return Err(anyhow!("The server rejected the username or password"));
+        }
+
+        if !status.is_success() {
+            return Err(anyhow!("{} returned {}", url, status));
+        }
+
+        Ok(())
+    }
+
+    /// A name for a downloaded profile: the profile id Access Server puts
+    /// in a comment, e.g. `user@vpn.example.com/AUTOLOGIN`, or else
+    /// `username@host`.
+    pub fn profile_name(&self, content: &str) -> String {
+        let profile_id = content.lines().find_map(|line| {
+            line.trim()
+                .strip_prefix("# OVPN_ACCESS_SERVER_PROFILE=")
+                .map(|id| id.trim().to_string())
+        });
+
+        if let Some(profile_id) = profile_id.filter(|id| !id.is_empty()) {
+            return profile_id;
+        }
+
+        let host = self
+            .base_url
+            .split("://")
+            .nth(1)
+            .unwrap_or(&self.base_url)
+            .split('/')
+            .next()
+            .unwrap_or_default();
+
+        format!("{}@{}", self.username, host)
+    }
+}
+
+fn normalize_url(url: &str) -> Result<String> {
+    let url = url.trim().trim_end_matches('/');
+    let url = if url.contains("://") {
+        url.to_string()
+    } else {
+        format!("https://{}", url)
+    };
+
+    let parsed =
+        reqwest::Url::parse(&url).with_context(|| format!("{} is not a valid URL", url))?;
+
+    match parsed.scheme() {
+        "https" => Ok(url),
+        "http" if is_loopback(&parsed) => Ok(url),
+        "http" => Err(anyhow!(
+            "Refusing to send credentials over plain http to {}, use https://",
+            parsed.host_str().unwrap_or_default()
+        )),
+        scheme => Err(anyhow!("Unsupported URL scheme {}", scheme)),
+    }
+}
+
+fn is_loopback(url: &reqwest::Url) -> bool {
+    let host = url.host_str().unwrap_or_default();
+
+    match host.trim_matches(['[', ']']).parse::<std::net::IpAddr>() {
+        Ok(address) => address.is_loopback(),
+        Err(_) => host == "localhost",
+    }
+}
+
+/// Extracts the message of an Access Server error such as
+/// `<Error><Type>Authorization Required</Type><Synopsis>...</Synopsis></Error>`.
+fn error_synopsis(body: &str) -> Option<String> {
+    if !body.contains("<Error>") {
+        return None;
+    }
+
+    let tag_content = |tag: &str| {
+        let start = body.find(&format!("<{}>", tag))? + tag.len() + 2;
+        let end = body[start..].find(&format!("</{}>", tag))? + start;
+        Some(body[start..end].trim().to_string())
+    };
+
+    tag_content("Synopsis")
+        .or_else(|| tag_content("Type"))
+        .or_else(|| Some("The server returned an error".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::{Arc, Mutex};
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    const PROFILE: &str = "client\nremote vpn.example.com 1194\n";
+
+    /// Starts a local stand-in for an Access Server answering with
+    /// `respond(method, path)`. Returns its URL and the requests it got.
+    async fn stand_in(
+        respond: fn(&str, &str) -> (u16, &'static str),
+    ) -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(vec![]));
+        let seen = requests.clone();
+
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut request = vec![];
+                let mut buffer = [0; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    match stream.read(&mut buffer).await {
+                        Ok(0) | Err(_) => break,
+                        Ok(read) => request.extend_from_slice(&buffer[..read]),
+                    }
+                }
+
+                let request = String::from_utf8_lossy(&request).to_string();
+                let mut line = request.split_whitespace();
+                let method = line.next().unwrap_or_default();
+                let path = line.next().unwrap_or_default();
+                seen.lock().unwrap().push(format!("{} {}", method, path));
+
+                let (status, body) = respond(method, path);
+                let mut response = format!(
+                    "HTTP/1.1 {} X\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    status,
+                    body.len()
+                );
+                if method != "HEAD" {
+                    response.push_str(body);
+                }
+                let _ = stream.write_all(response.as_bytes()).await;
+                let _ = stream.shutdown().await;
+            }
+        });
+
+        (url, requests)
+    }
+
+    #[test]
+    fn allows_http_only_for_loopback_hosts() {
+        assert_eq!(
+            normalize_url(" vpn.example.com/ ").unwrap(),
+            "https://vpn.example.com"
+        );
+        assert!(normalize_url("http://localhost:8080").is_ok());
+        assert!(normalize_url("http://127.0.0.1:8080").is_ok());
+        assert!(normalize_url("http://[::1]:8080").is_ok());
+        assert!(normalize_url("http://vpn.example.com").is_err());
+        assert!(normalize_url("http://localhost.example.com").is_err());
+        assert!(normalize_url("ftp://vpn.example.com").is_err());
+    }
+
+    #[tokio::test]
+    async fn downloads_a_profile() {
+        let (url, requests) = stand_in(|_, path| match path {
+            "/rest/GetAutologin" => (200, PROFILE),
+            _ => (404, ""),
+        })
+        .await;
+
+        let server = ProfileServer::new(&url, "user", "secret").unwrap();
+        let profile = server.download(ProfileType::Autologin).await.unwrap();
+
+        assert_eq!(profile, PROFILE);
+        assert_eq!(*requests.lock().unwrap(), ["GET /rest/GetAutologin"]);
+    }
+
+    #[tokio::test]
+    async fn reports_server_errors() {
+        let (url, _) = stand_in(|_, path| match path {
+            "/rest/GetUserlogin" => (401, ""),
+            "/rest/GetAutologin" => (
+                200,
+                "<Error><Type>Authorization Required</Type>\
+                 <Synopsis>Autologin is not allowed</Synopsis></Error>",
+            ),
+            _ => (200, "<html>not a profile</html>"),
+        })
+        .await;
+
+        let server = ProfileServer::new(&url, "user", "secret").unwrap();
+        let error = |result: Result<String>| format!("{:#}", result.unwrap_err());
+
+        assert_eq!(
+            error(server.download(ProfileType::Userlogin).await),
+            "The server rejected the username or password"
+        );
+        assert_eq!(
+            error(server.download(ProfileType::Autologin).await),
+            "Autologin is not allowed"
+        );
+        assert!(error(server.download(ProfileType::Generic).await)
+            .contains("did not return an OpenVPN profile"));
+    }
+
+    #[tokio::test]
+    async fn checks_profile_types_without_downloading() {
+        let (url, requests) = stand_in(|_, path| match path {
+            "/rest/GetAutologin" => (403, ""),
+            _ => (200, PROFILE),
+        })
+        .await;
+
+        let server = ProfileServer::new(&url, "user", "secret").unwrap();
+        let availability = server.available_profile_types().await;
+
+        let available: Vec<bool> = availability.iter().map(|item| item.available).collect();
+        assert_eq!(available, [true, false, true]);
+        assert!(requests
+            .lock()
+            .unwrap()
+            .iter()
+            .all(|request| request.starts_with("HEAD ")));
+    }
+}
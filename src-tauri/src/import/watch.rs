@@ -0,0 +1,356 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use tokio::sync::{broadcast, mpsc, Mutex};
+
+use crate::dbus::openvpn3::OpenVPN3Dbus;
+use crate::ovpn::prepare;
+use crate::settings::{ConfigSource, SettingsStore, WatchedDirectory};
+
+use super::directory::{self, ImportResult};
+use super::replace;
+
+/// How long to wait for a burst of file events to settle before syncing,
+/// so that a profile being copied in is only imported once it is complete.
+const DEBOUNCE: Duration = Duration::from_secs(2);
+
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncReport {
+    pub directory: String,
+    /// Names of the configurations affected by each kind of change
+    pub imported: Vec<String>,
+    pub reimported: Vec<String>,
+    pub removed: Vec<String>,
+    pub failed: Vec<String>,
+    pub summary: String,
+}
+
+impl SyncReport {
+    fn new(directory: &str) -> Self {
+        Self {
+            directory: directory.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.imported.is_empty()
+            && self.reimported.is_empty()
+            && self.removed.is_empty()
+            && self.failed.is_empty()
+    }
+
+    fn summarize(&mut self) {
+        let mut parts = vec![];
+        for (count, action) in [
+            (self.imported.len(), "imported"),
+            (self.reimported.len(), "updated"),
+            (self.removed.len(), "removed"),
+            (self.failed.len(), "failed"),
+        ] {
+            if count > 0 {
+                parts.push(format!("{} {}", count, action));
+            }
+        }
+
+        self.summary = if parts.is_empty() {
+            format!("{} is up to date", self.directory)
+        } else {
+            format!("{}: {}", self.directory, parts.join(", "))
+        };
+    }
+}
+
+/// Watches the directories listed in the settings and keeps the imported
+/// configurations in sync with the files in them.
+pub struct DirectoryWatcher {
+    openvpn3: Arc<OpenVPN3Dbus>,
+    settings: Arc<SettingsStore>,
+    sync_sender: broadcast::Sender<SyncReport>,
+    watcher: std::sync::Mutex<Option<RecommendedWatcher>>,
+    /// Serialises syncs started by file events and by the user
+    sync_lock: Arc<Mutex<()>>,
+}
+
+impl DirectoryWatcher {
+    pub fn new(openvpn3: Arc<OpenVPN3Dbus>, settings: Arc<SettingsStore>) -> Self {
+        let (sync_sender, _) = broadcast::channel::<SyncReport>(16);
+
+        Self {
+            openvpn3,
+            settings,
+            sync_sender,
+            watcher: std::sync::Mutex::new(None),
+            sync_lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Calls `callback` after every sync that changed something.
+    pub fn on_sync<F>(&self, callback: F)
+    where
+        F: Fn(SyncReport) + Send + 'static,
+    {
+        let mut rx = self.sync_sender.subscribe();
+
+        tokio::spawn(async move {
+            while let Ok(report) = rx.recv().await {
+                callback(report);
+            }
+        });
+    }
+
+    /// (Re)starts watching the directories currently in the settings and
+    /// syncs each of them once, picking up changes made while Konewka was
+    /// not running.
+    pub fn start(&self) -> Result<()> {
+        let watched = self.settings.get().watched_directories;
+        let (tx, mut rx) = mpsc::unbounded_channel::<PathBuf>();
+
+        let event_tx = tx.clone();
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| match event {
+                Ok(event) if event.kind.is_access() => (),
+                Ok(event) => {
+                    for path in event.paths {
+                        let _ = event_tx.send(path);
+                    }
+                }
+                Err(error) => tracing::warn!("Directory watcher error: {}", error),
+            })
+            .with_context(|| "Failed to create directory watcher")?;
+
+        for directory in watched.iter() {
+            let mode = if directory.recursive {
+                RecursiveMode::Recursive
+            } else {
+                RecursiveMode::NonRecursive
+            };
+
+            match watcher.watch(Path::new(&directory.path), mode) {
+                Ok(_) => {
+                    tracing::info!("Watching {} for configuration changes", directory.path);
+                    let _ = tx.send(PathBuf::from(&directory.path));
+                }
+                Err(error) => tracing::warn!("Failed to watch {}: {}", directory.path, error),
+            }
+        }
+
+        // Replacing the watcher drops the previous one and with it the
+        // sender its task is waiting on, which ends that task
+        *self.watcher.lock().unwrap() = Some(watcher);
+        drop(tx);
+
+        let openvpn3 = self.openvpn3.clone();
+        let settings = self.settings.clone();
+        let sync_sender = self.sync_sender.clone();
+        let sync_lock = self.sync_lock.clone();
+
+        tokio::spawn(async move {
+            while let Some(path) = rx.recv().await {
+                let mut changed = vec![path];
+                while let Ok(Some(path)) = tokio::time::timeout(DEBOUNCE, rx.recv()).await {
+                    changed.push(path);
+                }
+
+                let _guard = sync_lock.lock().await;
+                for directory in watched.iter() {
+                    if !changed.iter().any(|path| path.starts_with(&directory.path)) {
+                        continue;
+                    }
+
+                    match sync_directory(&openvpn3, &settings, directory).await {
+                        Ok(report) if report.is_empty() => (),
+                        Ok(report) => {
+                            let _ = sync_sender.send(report);
+                        }
+                        Err(error) => {
+                            tracing::error!("Failed to sync {}: {:#}", directory.path, error)
+                        }
+                    }
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Syncs every watched directory right away.
+    pub async fn sync_all(&self) -> Result<Vec<SyncReport>> {
+        let _guard = self.sync_lock.lock().await;
+        let mut reports = vec![];
+
+        for directory in self.settings.get().watched_directories.iter() {
+            let report = sync_directory(&self.openvpn3, &self.settings, directory).await?;
+            if !report.is_empty() {
+                let _ = self.sync_sender.send(report.clone());
+            }
+            reports.push(report);
+        }
+
+        Ok(reports)
+    }
+}
+
+/// Brings the configurations imported from `watched` up to date: new files
+/// are imported, changed files are imported again under the name and with
+/// the settings of the configuration they replace, and configurations whose
+/// file is gone are removed if the directory asks for it.
+pub async fn sync_directory(
+    openvpn3: &OpenVPN3Dbus,
+    settings: &SettingsStore,
+    watched: &WatchedDirectory,
+) -> Result<SyncReport> {
+    let dir = Path::new(&watched.path);
+    let mut report = SyncReport::new(&watched.path);
+
+    let files = directory::scan(dir, watched.recursive)?;
+    // A configuration missing from the listing would look untracked and
+    // get imported a second time
+    let configs = openvpn3.get_configs().await?;
+    if let Some(error) = configs.errors.first() {
+        return Err(anyhow!("Failed to read {}: {}", error.path, error.error));
+    }
+    let configs = configs.items;
+
+    let tracked: Vec<(String, String, ConfigSource)> = settings
+        .get()
+        .configs
+        .into_iter()
+        .filter_map(|(config_path, config)| {
+            let source = config.source?;
+            let name = configs
+                .iter()
+                .find(|config| config.path == config_path)?
+                .name
+                .clone();
+            covers(watched, Path::new(&source.file)).then_some((config_path, name, source))
+        })
+        .collect();
+
+    let tracked_files: HashSet<&str> = tracked
+        .iter()
+        .map(|(_, _, source)| source.file.as_str())
+        .collect();
+
+    for (config_path, name, source) in tracked.iter() {
+        let file = Path::new(&source.file);
+
+        if !file.exists() {
+            if !watched.remove_missing {
+                continue;
+            }
+
+            match openvpn3.remove_config(config_path.clone()).await {
+                Ok(_) => {
+                    if let Err(error) = settings.update(|settings| {
+                        settings.configs.remove(config_path);
+                    }) {
+                        tracing::warn!("Failed to forget settings of {}: {:#}", config_path, error);
+                    }
+                    report.removed.push(name.clone());
+                }
+                Err(error) => {
+                    tracing::error!("Failed to remove {}: {:#}", config_path, error);
+                    report.failed.push(name.clone());
+                }
+            }
+            continue;
+        }
+
+        let raw = match std::fs::read(file) {
+            Ok(raw) => raw,
+            Err(error) => {
+                tracing::warn!("Failed to read {}: {}", source.file, error);
+                continue;
+            }
+        };
+
+        let hash = directory::content_hash(&raw);
+        if hash == source.hash {
+            continue;
+        }
+
+        match reimport(openvpn3, settings, config_path, name, file, hash).await {
+            Ok(_) => report.reimported.push(name.clone()),
+            Err(error) => {
+                tracing::error!("Failed to re-import {}: {:#}", source.file, error);
+                report.failed.push(name.clone());
+            }
+        }
+    }
+
+    let new_files: Vec<PathBuf> = files
+        .into_iter()
+        .filter(|file| !tracked_files.contains(file.display().to_string().as_str()))
+        .collect();
+
+    if !new_files.is_empty() {
+        let candidates = directory::preview(openvpn3, settings, dir, &new_files).await?;
+        let outcome = directory::import(openvpn3, settings, candidates, false, true).await;
+
+        for file in outcome.files {
+            match file.result {
+                ImportResult::Imported { .. } => report.imported.push(file.config_name),
+                ImportResult::Failed { .. } => report.failed.push(file.config_name),
+                ImportResult::Skipped { .. } => (),
+            }
+        }
+    }
+
+    report.summarize();
+    if !report.is_empty() {
+        tracing::info!("{}", report.summary);
+    }
+
+    Ok(report)
+}
+
+/// Imports the new content of `file` in place of `config_path`.
+async fn reimport(
+    openvpn3: &OpenVPN3Dbus,
+    settings: &SettingsStore,
+    config_path: &str,
+    name: &str,
+    file: &Path,
+    hash: String,
+) -> Result<()> {
+    let prepared = prepare::prepare_file(file)?;
+    if !prepared.report.valid {
+        return Err(anyhow::anyhow!("{}", prepared.report.error_summary()));
+    }
+
+    let source = ConfigSource {
+        file: file.display().to_string(),
+        hash,
+    };
+
+    replace::replace_config(
+        openvpn3,
+        settings,
+        config_path,
+        name,
+        prepared.content,
+        |config| config.source = Some(source),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Whether `file` belongs to the watched directory, taking into account
+/// whether subdirectories are watched.
+fn covers(watched: &WatchedDirectory, file: &Path) -> bool {
+    let dir = Path::new(&watched.path);
+
+    if watched.recursive {
+        file.starts_with(dir)
+    } else {
+        file.parent() == Some(dir)
+    }
+}
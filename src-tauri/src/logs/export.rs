@@ -0,0 +1,144 @@
+use std::io::Write;
+
+use anyhow::{Context, Result};
+use chrono::{Local, SecondsFormat, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::dbus::codes::{
+    log_category_name, log_group_name, status_major_name, status_minor_name,
+    STATUS_MINOR_CONN_AUTH_FAILED, STATUS_MINOR_CONN_FAILED,
+};
+use crate::utils;
+
+use super::history::LogRecord;
+
+// RFC 5424 facility "user-level messages"
+const SYSLOG_FACILITY_USER: u32 = 1;
+// Private enterprise number reserved for documentation (RFC 5612)
+const SYSLOG_ENTERPRISE_ID: u32 = 32473;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum LogExportFormat {
+    JsonLines,
+    Text,
+    Syslog,
+}
+
+pub fn write_records<W: Write>(
+    writer: &mut W,
+    records: &[LogRecord],
+    format: LogExportFormat,
+) -> Result<()> {
+    let hostname = utils::hostname();
+
+    for record in records {
+        let line = match format {
+            LogExportFormat::JsonLines => {
+                serde_json::to_string(record).with_context(|| "Failed to serialize log record")?
+            }
+            LogExportFormat::Text => format_text(record),
+            LogExportFormat::Syslog => format_syslog(record, &hostname),
+        };
+
+        writeln!(writer, "{}", line).with_context(|| "Failed to write log record")?;
+    }
+
+    // A buffered writer would otherwise report write errors only on drop,
+    // where they are lost
+    writer
+        .flush()
+        .with_context(|| "Failed to write log records")?;
+
+    Ok(())
+}
+
+fn format_text(record: &LogRecord) -> String {
+    let timestamp = match Local.timestamp_millis_opt(record.timestamp).single() {
+        Some(timestamp) => timestamp.format("%Y-%m-%d %H:%M:%S%.3f").to_string(),
+        None => record.timestamp.to_string(),
+    };
+
+    format!(
+        "{} [{}] {} {}: {}",
+        timestamp,
+        record.log.path,
+        record.log.member,
+        describe_flags(record),
+        indent_continuation(record.log.message.trim_end())
+    )
+}
+
+fn format_syslog(record: &LogRecord, hostname: &str) -> String {
+    let timestamp = match Utc.timestamp_millis_opt(record.timestamp).single() {
+        Some(timestamp) => timestamp.to_rfc3339_opts(SecondsFormat::Millis, true),
+        None => "-".to_string(),
+    };
+
+    let priority = SYSLOG_FACILITY_USER * 8 + syslog_severity(record);
+
+    format!(
+        "<{}>1 {} {} konewka {} {} [konewka@{} session=\"{}\" flags=\"{}\"] {}",
+        priority,
+        timestamp,
+        hostname,
+        std::process::id(),
+        record.log.member,
+        SYSLOG_ENTERPRISE_ID,
+        escape_param_value(&record.log.path),
+        escape_param_value(&describe_flags(record)),
+        escape_newlines(record.log.message.trim_end())
+    )
+}
+
+fn describe_flags(record: &LogRecord) -> String {
+    if record.log.member == "StatusChange" {
+        format!(
+            "{}/{}",
+            status_major_name(record.log.first_flag),
+            status_minor_name(record.log.second_flag)
+        )
+    } else {
+        format!(
+            "{}/{}",
+            log_group_name(record.log.first_flag),
+            log_category_name(record.log.second_flag)
+        )
+    }
+}
+
+fn syslog_severity(record: &LogRecord) -> u32 {
+    if record.log.member == "StatusChange" {
+        return match record.log.second_flag {
+            STATUS_MINOR_CONN_FAILED | STATUS_MINOR_CONN_AUTH_FAILED => 3,
+            _ => 5,
+        };
+    }
+
+    match log_category_name(record.log.second_flag) {
+        "DEBUG" | "VERB2" | "VERB1" => 7,
+        "WARN" => 4,
+        "ERROR" => 3,
+        "CRIT" => 2,
+        "FATAL" => 0,
+        _ => 6,
+    }
+}
+
+/// Keeps one record per line start in the text format, continuation lines
+/// of multi-line messages are indented.
+fn indent_continuation(message: &str) -> String {
+    message.replace('\n', "\n    ")
+}
+
+/// Syslog consumers expect one record per line.
+fn escape_newlines(message: &str) -> String {
+    message.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn escape_param_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace(']', "\\]")
+}
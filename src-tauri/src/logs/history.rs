@@ -0,0 +1,64 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::structs::LogMessage;
+
+const MAX_RECORDS: usize = 10_000;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LogRecord {
+    /// Milliseconds since the Unix epoch at which the signal was received.
+    pub timestamp: i64,
+    #[serde(flatten)]
+    pub log: LogMessage,
+}
+
+/// In-memory history of every log and status signal forwarded to the UI,
+/// bounded to the most recent `MAX_RECORDS` entries.
+pub struct LogHistory {
+    records: Mutex<VecDeque<LogRecord>>,
+}
+
+impl LogHistory {
+    pub fn new() -> Self {
+        Self {
+            records: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    pub fn push(&self, log: LogMessage) {
+        let record = LogRecord {
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            log,
+        };
+
+        let mut records = self.records.lock().unwrap();
+        if records.len() == MAX_RECORDS {
+            records.pop_front();
+        }
+        records.push_back(record);
+    }
+
+    /// Returns records for `session_path` (or every session when `None`)
+    /// received within the inclusive `from`..`to` range of timestamps.
+    // `Option::is_none_or` would need Rust 1.82
+    #[allow(clippy::unnecessary_map_or)]
+    pub fn query(
+        &self,
+        session_path: Option<&str>,
+        from: Option<i64>,
+        to: Option<i64>,
+    ) -> Vec<LogRecord> {
+        let records = self.records.lock().unwrap();
+
+        records
+            .iter()
+            .filter(|record| session_path.map_or(true, |path| record.log.path == path))
+            .filter(|record| from.map_or(true, |from| record.timestamp >= from))
+            .filter(|record| to.map_or(true, |to| record.timestamp <= to))
+            .cloned()
+            .collect()
+    }
+}
@@ -3,36 +3,83 @@
 
 use std::sync::Arc;
 
+use connection::failover::FailoverManager;
 use dbus::openvpn3::OpenVPN3Dbus;
+use import::watch::DirectoryWatcher;
 
 use commands::{
-    connect_session, disconnect_session, exit_app, get_openvpn3_configs, get_openvpn3_sessions,
-    import_openvpn3_config, minimize_to_tray, new_tunnel, remove_config, select_file,
+    connect_config, connect_connection_set, connect_failover_group, connect_session,
+    connect_to_remote, disconnect_connection_set, disconnect_failover_group, disconnect_session,
+    exit_app, export_config, export_logs, generate_diagnostics_bundle, get_config_access,
+    get_config_log_verbosity, get_config_overrides, get_config_protocol_fallback,
+    get_config_remotes, get_config_summary, get_connection_sets, get_failover_groups,
+    get_networkmanager_connections, get_openvpn3_configs, get_openvpn3_sessions,
+    get_override_definitions, get_server_profile_types, get_session_log_verbosity,
+    get_signal_scope, get_watched_directories, grant_config_access, import_directory,
+    import_from_server, import_networkmanager_connection, import_openvpn3_config,
+    import_openvpn3_config_content, minimize_to_tray, new_tunnel, preview_directory_import,
+    rank_config_remotes, refresh_server_config, remove_config, rename_config, revoke_config_access,
+    seal_config, select_file, set_config_auto_select_remote, set_config_locked_down,
+    set_config_log_verbosity, set_config_override, set_config_protocol_fallback,
+    set_config_public_access, set_connection_sets, set_failover_groups, set_session_log_forward,
+    set_session_log_verbosity, set_signal_scope, set_watched_directories, sync_watched_directories,
+    validate_config_content, validate_config_file,
 };
-use structs::LogMessage;
-use tauri::{CustomMenuItem, Manager, SystemTray, SystemTrayEvent, SystemTrayMenu};
+use logs::history::LogHistory;
+use settings::SettingsStore;
+use tauri::{Manager, SystemTray, SystemTrayEvent};
 
+mod access;
+mod cli;
 mod commands;
+mod connection;
 mod dbus;
+mod diagnostics;
+mod import;
+mod logs;
+mod ovpn;
+mod settings;
 mod structs;
+mod telemetry;
+mod tray;
 mod utils;
 
 struct MyState {
     openvpn3: Arc<OpenVPN3Dbus>,
+    log_history: Arc<LogHistory>,
+    settings: Arc<SettingsStore>,
+    directory_watcher: Arc<DirectoryWatcher>,
+    failover: Arc<FailoverManager>,
 }
 
 #[tokio::main]
 async fn main() {
-    let show = CustomMenuItem::new("show".to_string(), "Show");
-    let tray_menu = SystemTrayMenu::new().add_item(show);
-    let tray = SystemTray::new().with_menu(tray_menu);
+    let _log_guard = telemetry::init();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let command = match cli::parse(&args) {
+        Ok(command) => command,
+        Err(error) => {
+            eprintln!("{:#}", error);
+            std::process::exit(2);
+        }
+    };
 
     let openvpn3 = Arc::new(dbus::openvpn3::OpenVPN3Dbus::new().unwrap());
 
+    let settings = Arc::new(SettingsStore::load());
+
+    if let Some(command) = command {
+        std::process::exit(cli::run(command, &openvpn3, &settings).await);
+    }
+
+    let tray = SystemTray::new().with_menu(tray::menu(&settings.get().connection_sets));
+    openvpn3.set_signal_scope(settings.get().signal_scope);
+
     match openvpn3.signals().await {
-        Ok(_) => println!("Successfully connected to OpenVPN3 D-Bus - signals"),
-        Err(_) => {
-            println!("Failed to connect to OpenVPN3 D-Bus - signals");
+        Ok(_) => tracing::info!("Successfully connected to OpenVPN3 D-Bus - signals"),
+        Err(error) => {
+            tracing::error!("Failed to connect to OpenVPN3 D-Bus - signals: {:#}", error);
             std::process::exit(1);
         }
     }
@@ -40,8 +87,23 @@ async fn main() {
     let openvpn3_logger = openvpn3.clone();
     let openvpn3_window_events = openvpn3.clone();
 
+    let log_history = Arc::new(LogHistory::new());
+    let log_history_logger = log_history.clone();
+
+    let directory_watcher = Arc::new(DirectoryWatcher::new(openvpn3.clone(), settings.clone()));
+    let directory_watcher_events = directory_watcher.clone();
+
+    let failover = Arc::new(FailoverManager::new(openvpn3.clone(), settings.clone()));
+    let failover_events = failover.clone();
+
     let app = tauri::Builder::default()
-        .manage(MyState { openvpn3: openvpn3 })
+        .manage(MyState {
+            openvpn3: openvpn3,
+            log_history: log_history,
+            settings: settings,
+            directory_watcher: directory_watcher,
+            failover: failover,
+        })
         .setup(move |app| {
             #[cfg(debug_assertions)] // only include this code on debug builds
             {
@@ -70,14 +132,23 @@ async fn main() {
                 }
             });
 
-            openvpn3_logger.on_log(move |path, member, group, level, message| {
-                let message = LogMessage {
-                    path: path,
-                    member: member,
-                    first_flag: group,
-                    second_flag: level,
-                    message,
-                };
+            let sync_window = main_window.clone();
+            directory_watcher_events.on_sync(move |report| {
+                sync_window.emit("directory_synced", report).unwrap();
+            });
+            // Started only now so that the report of the initial sync reaches
+            // the window
+            if let Err(error) = directory_watcher_events.start() {
+                tracing::error!("Failed to watch config directories: {:#}", error);
+            }
+
+            let failover_window = main_window.clone();
+            failover_events.on_event(move |event| {
+                failover_window.emit("failover", event).unwrap();
+            });
+
+            openvpn3_logger.on_log(move |message| {
+                log_history_logger.push(message.clone());
                 main_window.emit("log", message).unwrap();
             });
 
@@ -85,13 +156,7 @@ async fn main() {
         })
         .system_tray(tray)
         .on_system_tray_event(|app, event| match event {
-            SystemTrayEvent::MenuItemClick { id, .. } => match id.as_str() {
-                "show" => {
-                    let window = app.get_window("main").unwrap();
-                    window.show().unwrap();
-                }
-                _ => {}
-            },
+            SystemTrayEvent::MenuItemClick { id, .. } => tray::on_menu_item_click(app, &id),
             _ => {}
         })
         .invoke_handler(tauri::generate_handler![
@@ -105,6 +170,55 @@ async fn main() {
             connect_session,
             exit_app,
             minimize_to_tray,
+            export_logs,
+            generate_diagnostics_bundle,
+            get_session_log_verbosity,
+            set_session_log_verbosity,
+            set_session_log_forward,
+            get_config_log_verbosity,
+            set_config_log_verbosity,
+            get_signal_scope,
+            set_signal_scope,
+            validate_config_file,
+            get_networkmanager_connections,
+            import_networkmanager_connection,
+            preview_directory_import,
+            import_directory,
+            get_watched_directories,
+            set_watched_directories,
+            sync_watched_directories,
+            validate_config_content,
+            import_openvpn3_config_content,
+            get_server_profile_types,
+            import_from_server,
+            refresh_server_config,
+            export_config,
+            get_config_summary,
+            rename_config,
+            get_override_definitions,
+            get_config_overrides,
+            set_config_override,
+            get_config_remotes,
+            connect_to_remote,
+            rank_config_remotes,
+            set_config_auto_select_remote,
+            connect_config,
+            get_config_protocol_fallback,
+            set_config_protocol_fallback,
+            get_failover_groups,
+            set_failover_groups,
+            connect_failover_group,
+            disconnect_failover_group,
+            get_connection_sets,
+            set_connection_sets,
+            connect_connection_set,
+            disconnect_connection_set,
+            get_config_access,
+            grant_config_access,
+            revoke_config_access,
+            set_config_public_access,
+            set_config_locked_down,
+            seal_config,
         ])
         .build(tauri::generate_context!())
         .unwrap();
@@ -0,0 +1,9 @@
+pub mod bundle;
+pub mod inline;
+pub mod networkmanager;
+pub mod parser;
+pub mod prepare;
+pub mod remotes;
+pub mod secrets;
+pub mod summary;
+pub mod validate;
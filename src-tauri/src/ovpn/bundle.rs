@@ -0,0 +1,362 @@
+use std::collections::BTreeMap;
+use std::io::{self, Read};
+use std::path::{Component, Path};
+
+use anyhow::{anyhow, Context, Result};
+
+use super::prepare::{self, PreparedConfig};
+
+const CONFIG_EXTENSIONS: [&str; 2] = ["ovpn", "conf"];
+const BUNDLE_EXTENSIONS: [&str; 2] = ["tblk", "visc"];
+
+/// Limits on what is extracted from a zip archive, far above any real
+/// profile bundle, so that a zip bomb cannot exhaust memory.
+const MAX_ZIP_ENTRIES: usize = 1000;
+const MAX_ZIP_ENTRY_SIZE: u64 = 4 * 1024 * 1024;
+const MAX_ZIP_TOTAL_SIZE: u64 = 32 * 1024 * 1024;
+
+/// A single configuration found in the file or bundle being imported.
+#[derive(Debug, Clone)]
+pub struct ImportProfile {
+    /// Location of the configuration inside the bundle
+    pub source: String,
+    pub name: String,
+    pub prepared: PreparedConfig,
+}
+
+/// Files of a bundle keyed by their path relative to the bundle root,
+/// always using `/` as separator.
+type BundleFiles = BTreeMap<String, Vec<u8>>;
+
+/// Loads every configuration contained in `path`, which can be a plain
+/// .ovpn file, a zip archive or a Tunnelblick (.tblk) or Viscosity (.visc)
+/// bundle, either as a directory or zipped. Files referenced by a
+/// configuration are looked up inside the same bundle and inlined.
+pub fn load(path: &Path) -> Result<Vec<ImportProfile>> {
+    let files = if path.is_dir() {
+        if !has_extension(path, &BUNDLE_EXTENSIONS) {
+            return Err(anyhow!(
+                "{} is a directory, only .tblk and .visc bundles can be imported",
+                path.display()
+            ));
+        }
+        let root = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let mut files = BundleFiles::new();
+        read_directory(path, &root, &mut files)?;
+        files
+    } else if is_zip(path)? {
+        read_zip(path)?
+    } else {
+        let prepared = prepare::prepare_file(path)?;
+        return Ok(vec![ImportProfile {
+            source: path.display().to_string(),
+            name: file_stem(&path.to_string_lossy()),
+            prepared,
+        }]);
+    };
+
+    let profiles: Vec<ImportProfile> = files
+        .keys()
+        .filter(|name| is_config(name))
+        .map(|name| load_profile(&files, name))
+        .collect::<Result<_>>()?;
+
+    if profiles.is_empty() {
+        return Err(anyhow!(
+            "No OpenVPN configuration found in {}",
+            path.display()
+        ));
+    }
+
+    Ok(profiles)
+}
+
+fn load_profile(files: &BundleFiles, config_path: &str) -> Result<ImportProfile> {
+    let content = String::from_utf8(files[config_path].clone())
+        .with_context(|| format!("{} is not a text file", config_path))?;
+    let dir = parent(config_path);
+
+    let prepared = prepare::prepare_content(&content, |file| {
+        resolve(files, dir, file)
+            .map(|data| data.to_vec())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "not found in bundle"))
+    });
+
+    Ok(ImportProfile {
+        source: config_path.to_string(),
+        name: profile_name(config_path, &content),
+        prepared,
+    })
+}
+
+/// Looks a referenced file up relative to the configuration first. Bundles
+/// built on another machine often keep absolute paths, so as a fallback the
+/// file name alone is matched next to the configuration and then anywhere
+/// in the bundle, as long as it is unambiguous.
+fn resolve<'a>(files: &'a BundleFiles, dir: &str, file: &str) -> Option<&'a [u8]> {
+    if let Some(path) = join(dir, file) {
+        if let Some(data) = files.get(&path) {
+            return Some(data);
+        }
+    }
+
+    let file_name = file.rsplit(['/', '\\']).next()?;
+    if let Some(data) = files.get(&join(dir, file_name)?) {
+        return Some(data);
+    }
+
+    let mut matches = files
+        .iter()
+        .filter(|(path, _)| path.rsplit('/').next() == Some(file_name) && !is_metadata(path));
+    match (matches.next(), matches.next()) {
+        (Some((_, data)), None) => Some(data),
+        _ => None,
+    }
+}
+
+/// Prefers the name Viscosity stores in its configuration, then the name of
+/// the enclosing .tblk or .visc bundle, then the configuration file name.
+fn profile_name(config_path: &str, content: &str) -> String {
+    if let Some(name) = prepare::embedded_name(content) {
+        return name;
+    }
+
+    let bundle = config_path.rsplit('/').skip(1).find(|component| {
+        BUNDLE_EXTENSIONS
+            .iter()
+            .any(|extension| component.ends_with(&format!(".{}", extension)))
+    });
+    if let Some(bundle) = bundle {
+        return file_stem(bundle);
+    }
+
+    file_stem(config_path)
+}
+
+fn read_directory(dir: &Path, prefix: &str, files: &mut BundleFiles) -> Result<()> {
+    let entries =
+        std::fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))?;
+
+    for entry in entries {
+        let entry = entry?;
+        let path = entry.path();
+        let name = format!("{}/{}", prefix, entry.file_name().to_string_lossy());
+
+        let file_type = entry.file_type()?;
+        if file_type.is_symlink() {
+            // A link could point anywhere, e.g. at the keys of the user
+            tracing::warn!(
+                "Not following {}, links in bundles are skipped",
+                path.display()
+            );
+        } else if file_type.is_dir() {
+            read_directory(&path, &name, files)?;
+        } else {
+            let data = std::fs::read(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            files.insert(name, data);
+        }
+    }
+
+    Ok(())
+}
+
+fn read_zip(path: &Path) -> Result<BundleFiles> {
+    let file =
+        std::fs::File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut archive = zip::ZipArchive::new(file)
+        .with_context(|| format!("{} is not a valid zip archive", path.display()))?;
+    let mut files = BundleFiles::new();
+    let mut total_size = 0;
+
+    if archive.len() > MAX_ZIP_ENTRIES {
+        return Err(anyhow!(
+            "{} has {} entries, more than the {} a bundle may have",
+            path.display(),
+            archive.len(),
+            MAX_ZIP_ENTRIES
+        ));
+    }
+
+    for index in 0..archive.len() {
+        let entry = archive.by_index(index)?;
+        if entry.is_dir() {
+            continue;
+        }
+
+        // enclosed_name rejects entries escaping the archive root
+        let name = match entry.enclosed_name() {
+            Some(name) => name.to_string_lossy().replace('\\', "/"),
+            None => continue,
+        };
+
+        // The sizes in the archive can lie, so the limits apply to what is
+        // actually extracted
+        let mut data = vec![];
+        entry
+            .take(MAX_ZIP_ENTRY_SIZE + 1)
+            .read_to_end(&mut data)
+            .with_context(|| format!("Failed to extract {}", name))?;
+
+        if data.len() as u64 > MAX_ZIP_ENTRY_SIZE {
+            return Err(anyhow!(
+                "{} is larger than {} bytes",
+                name,
+                MAX_ZIP_ENTRY_SIZE
+            ));
+        }
+        total_size += data.len() as u64;
+        if total_size > MAX_ZIP_TOTAL_SIZE {
+            return Err(anyhow!(
+                "{} extracts to more than {} bytes",
+                path.display(),
+                MAX_ZIP_TOTAL_SIZE
+            ));
+        }
+
+        files.insert(name, data);
+    }
+
+    Ok(files)
+}
+
+fn is_zip(path: &Path) -> Result<bool> {
+    let mut magic = [0u8; 4];
+    let mut file =
+        std::fs::File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    match file.read_exact(&mut magic) {
+        Ok(_) => Ok(&magic == b"PK\x03\x04"),
+        Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(error) => Err(error).with_context(|| format!("Failed to read {}", path.display())),
+    }
+}
+
+fn is_config(path: &str) -> bool {
+    !is_metadata(path) && has_extension(Path::new(path), &CONFIG_EXTENSIONS)
+}
+
+/// Resource forks and folders added by the macOS archive utility.
+fn is_metadata(path: &str) -> bool {
+    path.split('/')
+        .any(|component| component == "__MACOSX" || component.starts_with("._"))
+}
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .is_some_and(|extension| extensions.contains(&extension.as_str()))
+}
+
+fn file_stem(path: &str) -> String {
+    Path::new(path)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string())
+}
+
+fn parent(path: &str) -> &str {
+    path.rsplit_once('/').map(|(dir, _)| dir).unwrap_or("")
+}
+
+/// Joins a relative reference onto a bundle directory, returning None for
+/// absolute paths and references leaving the bundle.
+fn join(dir: &str, file: &str) -> Option<String> {
+    let mut components: Vec<String> = dir
+        .split('/')
+        .filter(|component| !component.is_empty())
+        .map(str::to_string)
+        .collect();
+
+    for component in Path::new(&file.replace('\\', "/")).components() {
+        match component {
+            Component::Normal(name) => components.push(name.to_string_lossy().to_string()),
+            Component::CurDir => (),
+            Component::ParentDir => {
+                components.pop()?;
+            }
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+
+    Some(components.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    fn write_zip(name: &str, entries: &[(&str, &[u8])]) -> std::path::PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("konewka-{}-{}.zip", name, std::process::id()));
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+        for (entry, data) in entries {
+            zip.start_file(*entry, zip::write::FileOptions::default())
+                .unwrap();
+            zip.write_all(data).unwrap();
+        }
+        zip.finish().unwrap();
+        path
+    }
+
+    #[test]
+    fn loads_profiles_from_zip() {
+        let path = write_zip(
+            "bundle",
+            &[
+                (
+                    "work.tblk/config.ovpn",
+                    b"client\nremote vpn.example.com\nca ca.crt\n",
+                ),
+                ("work.tblk/ca.crt", b"CA\n"),
+            ],
+        );
+
+        let profiles = load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(profiles.len(), 1);
+        assert_eq!(profiles[0].name, "work");
+        assert!(profiles[0].prepared.content.contains("<ca>\nCA\n</ca>"));
+    }
+
+    #[test]
+    fn skips_links_out_of_bundle_directories() {
+        let dir = std::env::temp_dir().join(format!("konewka-links-{}", std::process::id()));
+        let bundle = dir.join("work.tblk");
+        std::fs::create_dir_all(&bundle).unwrap();
+        std::fs::write(dir.join("id_rsa"), "PRIVATE KEY\n").unwrap();
+        std::fs::write(
+            bundle.join("config.ovpn"),
+            "client\nremote vpn.example.com\nkey /home/user/.ssh/id_rsa\n",
+        )
+        .unwrap();
+        std::os::unix::fs::symlink(dir.join("id_rsa"), bundle.join("id_rsa")).unwrap();
+
+        let profiles = load(&bundle);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let profiles = profiles.unwrap();
+        assert_eq!(profiles.len(), 1);
+        assert!(!profiles[0].prepared.content.contains("PRIVATE KEY"));
+        assert!(!profiles[0].prepared.report.valid);
+    }
+
+    #[test]
+    fn refuses_oversized_zip_entries() {
+        let large = vec![0u8; MAX_ZIP_ENTRY_SIZE as usize + 1];
+        let path = write_zip(
+            "large",
+            &[("config.ovpn", b"client\n"), ("large.bin", &large)],
+        );
+
+        let error = load(&path).unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(format!("{:#}", error).contains("large.bin is larger than"));
+    }
+}
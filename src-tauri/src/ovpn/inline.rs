@@ -0,0 +1,145 @@
+use std::io;
+
+use base64::Engine;
+use serde::Serialize;
+
+use super::parser::{Diagnostic, Directive, InlineBlock, Item, OvpnConfig};
+
+/// Directives whose first argument names a file that can be replaced by an
+/// inline block of the same name.
+const FILE_DIRECTIVES: [&str; 11] = [
+    "ca",
+    "cert",
+    "key",
+    "extra-certs",
+    "tls-auth",
+    "tls-crypt",
+    "tls-crypt-v2",
+    "secret",
+    "pkcs12",
+    "dh",
+    "crl-verify",
+];
+
+#[derive(Serialize, Debug, Clone)]
+pub struct InlinedFile {
+    pub directive: String,
+    pub file: String,
+    pub line: usize,
+}
+
+/// Replaces directives such as `ca ca.crt` or `tls-auth ta.key 1` with the
+/// equivalent inline blocks, loading each file through `read`. A direction
+/// given to `tls-auth` or `secret` becomes a `key-direction` directive.
+/// Files that cannot be read are left in place and reported as errors.
+pub fn inline_external_files<F>(
+    config: &mut OvpnConfig,
+    read: F,
+) -> (Vec<InlinedFile>, Vec<Diagnostic>)
+where
+    F: Fn(&str) -> io::Result<Vec<u8>>,
+{
+    let mut inlined = vec![];
+    let mut diagnostics = vec![];
+    let mut items = Vec::with_capacity(config.items.len());
+    let has_key_direction = config.directive("key-direction").is_some();
+
+    for item in config.items.drain(..) {
+        let directive = match &item {
+            Item::Directive(directive) if is_file_reference(directive) => directive.clone(),
+            _ => {
+                items.push(item);
+                continue;
+            }
+        };
+
+        let file = &directive.args[0];
+        let data = match read(file) {
+            Ok(data) => data,
+            Err(error) => {
+                diagnostics.push(Diagnostic::error(
+                    directive.line,
+                    format!(
+                        "Failed to read {} referenced by {}: {}",
+                        file, directive.name, error
+                    ),
+                ));
+                items.push(item);
+                continue;
+            }
+        };
+
+        let content = if directive.name == "pkcs12" {
+            wrap_base64(&base64::engine::general_purpose::STANDARD.encode(&data))
+        } else {
+            match String::from_utf8(data) {
+                Ok(content) => content,
+                Err(_) => {
+                    diagnostics.push(Diagnostic::error(
+                        directive.line,
+                        format!(
+                            "{} referenced by {} is not a text file",
+                            file, directive.name
+                        ),
+                    ));
+                    items.push(item);
+                    continue;
+                }
+            }
+        };
+
+        inlined.push(InlinedFile {
+            directive: directive.name.clone(),
+            file: file.clone(),
+            line: directive.line,
+        });
+
+        let key_direction = match directive.name.as_str() {
+            "tls-auth" | "secret" => directive.args.get(1).cloned(),
+            _ => None,
+        };
+
+        items.push(Item::Block(InlineBlock {
+            tag: directive.name.clone(),
+            content,
+            line: directive.line,
+        }));
+
+        if let Some(key_direction) = key_direction {
+            if !has_key_direction {
+                items.push(Item::Directive(Directive {
+                    name: "key-direction".to_string(),
+                    args: vec![key_direction],
+                    line: directive.line,
+                }));
+            }
+        }
+    }
+
+    config.items = items;
+
+    (inlined, diagnostics)
+}
+
+fn is_file_reference(directive: &Directive) -> bool {
+    if !FILE_DIRECTIVES.contains(&directive.name.as_str()) {
+        return false;
+    }
+
+    match directive.args.first() {
+        Some(file) if file == "[inline]" => false,
+        // crl-verify <dir> dir points to a directory of CRLs
+        Some(_) if directive.name == "crl-verify" => directive.args.get(1).is_none(),
+        Some(_) => true,
+        None => false,
+    }
+}
+
+fn wrap_base64(encoded: &str) -> String {
+    let mut wrapped = String::with_capacity(encoded.len() + encoded.len() / 64 + 1);
+    for chunk in encoded.as_bytes().chunks(64) {
+        wrapped.push_str(std::str::from_utf8(chunk).unwrap());
+        wrapped.push('\n');
+    }
+    wrapped
+}
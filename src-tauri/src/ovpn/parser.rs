@@ -0,0 +1,466 @@
+use std::fmt;
+
+use serde::Serialize;
+
+/// Tags accepted by OpenVPN as `<tag>...</tag>` inline blocks.
+pub const INLINE_TAGS: [&str; 15] = [
+    "ca",
+    "cert",
+    "key",
+    "extra-certs",
+    "tls-auth",
+    "tls-crypt",
+    "tls-crypt-v2",
+    "secret",
+    "pkcs12",
+    "dh",
+    "crl-verify",
+    "auth-user-pass",
+    "http-proxy-user-pass",
+    "peer-fingerprint",
+    "connection",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Directive {
+    pub name: String,
+    pub args: Vec<String>,
+    pub line: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct InlineBlock {
+    pub tag: String,
+    pub content: String,
+    pub line: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Item {
+    Directive(Directive),
+    Block(InlineBlock),
+    Comment(String),
+    Blank,
+}
+
+/// A parsed configuration. Comments and blank lines are kept so that the
+/// configuration can be written back close to how it was read.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OvpnConfig {
+    pub items: Vec<Item>,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Diagnostic {
+    /// 1-based line number, 0 when the problem concerns the whole file
+    pub line: usize,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn error(line: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            severity: Severity::Error,
+            message: message.into(),
+        }
+    }
+
+    pub fn warning(line: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            severity: Severity::Warning,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.line == 0 {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "line {}: {}", self.line, self.message)
+        }
+    }
+}
+
+impl OvpnConfig {
+    pub fn directives(&self) -> impl Iterator<Item = &Directive> {
+        self.items.iter().filter_map(|item| match item {
+            Item::Directive(directive) => Some(directive),
+            _ => None,
+        })
+    }
+
+    pub fn blocks(&self) -> impl Iterator<Item = &InlineBlock> {
+        self.items.iter().filter_map(|item| match item {
+            Item::Block(block) => Some(block),
+            _ => None,
+        })
+    }
+
+    pub fn directive(&self, name: &str) -> Option<&Directive> {
+        self.directives().find(|directive| directive.name == name)
+    }
+
+    pub fn block(&self, tag: &str) -> Option<&InlineBlock> {
+        self.blocks().find(|block| block.tag == tag)
+    }
+}
+
+impl fmt::Display for OvpnConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for item in self.items.iter() {
+            match item {
+                Item::Directive(directive) => {
+                    write!(f, "{}", directive.name)?;
+                    for arg in directive.args.iter() {
+                        write!(f, " {}", quote(arg))?;
+                    }
+                    writeln!(f)?;
+                }
+                Item::Block(block) => {
+                    writeln!(f, "<{}>", block.tag)?;
+                    write!(f, "{}", block.content)?;
+                    if !block.content.is_empty() && !block.content.ends_with('\n') {
+                        writeln!(f)?;
+                    }
+                    writeln!(f, "</{}>", block.tag)?;
+                }
+                Item::Comment(comment) => writeln!(f, "{}", comment)?,
+                Item::Blank => writeln!(f)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses the content of an .ovpn file. Parsing never fails as a whole;
+/// problems are reported as diagnostics with the line they occur on.
+pub fn parse(content: &str) -> (OvpnConfig, Vec<Diagnostic>) {
+    let mut config = OvpnConfig::default();
+    let mut diagnostics = vec![];
+    let mut open_block: Option<InlineBlock> = None;
+
+    for (index, raw_line) in content.lines().enumerate() {
+        let line_number = index + 1;
+        let line = raw_line.trim();
+
+        if let Some(block) = open_block.as_mut() {
+            if let Some(tag) = closing_tag(line) {
+                if tag != block.tag {
+                    diagnostics.push(Diagnostic::error(
+                        line_number,
+                        format!(
+                            "</{}> does not close <{}> opened on line {}",
+                            tag, block.tag, block.line
+                        ),
+                    ));
+                    continue;
+                }
+
+                let block = open_block.take().unwrap();
+                if block.content.trim().is_empty() {
+                    diagnostics.push(Diagnostic::warning(
+                        block.line,
+                        format!("<{}> block is empty", block.tag),
+                    ));
+                }
+                config.items.push(Item::Block(block));
+            } else if opening_tag(line).is_some() {
+                diagnostics.push(Diagnostic::error(
+                    line_number,
+                    format!(
+                        "{} inside <{}> opened on line {}",
+                        line, block.tag, block.line
+                    ),
+                ));
+            } else {
+                block.content.push_str(raw_line);
+                block.content.push('\n');
+            }
+            continue;
+        }
+
+        if line.is_empty() {
+            config.items.push(Item::Blank);
+            continue;
+        }
+
+        if line.starts_with('#') || line.starts_with(';') {
+            config.items.push(Item::Comment(line.to_string()));
+            continue;
+        }
+
+        if let Some(tag) = closing_tag(line) {
+            diagnostics.push(Diagnostic::error(
+                line_number,
+                format!("</{}> without a matching <{}>", tag, tag),
+            ));
+            continue;
+        }
+
+        if let Some(tag) = opening_tag(line) {
+            if !INLINE_TAGS.contains(&tag) {
+                diagnostics.push(Diagnostic::warning(
+                    line_number,
+                    format!("Unknown inline block <{}>", tag),
+                ));
+            }
+            open_block = Some(InlineBlock {
+                tag: tag.to_string(),
+                content: String::new(),
+                line: line_number,
+            });
+            continue;
+        }
+
+        match tokenize(line) {
+            Ok(tokens) if tokens.is_empty() => config.items.push(Item::Blank),
+            Ok(mut tokens) => {
+                let name = tokens.remove(0);
+                let name = name.strip_prefix("--").unwrap_or(&name).to_string();
+                config.items.push(Item::Directive(Directive {
+                    name,
+                    args: tokens,
+                    line: line_number,
+                }));
+            }
+            Err(message) => diagnostics.push(Diagnostic::error(line_number, message)),
+        }
+    }
+
+    if let Some(block) = open_block {
+        diagnostics.push(Diagnostic::error(
+            block.line,
+            format!("<{}> is never closed", block.tag),
+        ));
+    }
+
+    (config, diagnostics)
+}
+
+fn opening_tag(line: &str) -> Option<&str> {
+    let tag = line.strip_prefix('<')?.strip_suffix('>')?;
+    if tag.starts_with('/') || tag.is_empty() || tag.contains(char::is_whitespace) {
+        return None;
+    }
+    Some(tag)
+}
+
+fn closing_tag(line: &str) -> Option<&str> {
+    let tag = line.strip_prefix("</")?.strip_suffix('>')?;
+    if tag.is_empty() || tag.contains(char::is_whitespace) {
+        return None;
+    }
+    Some(tag)
+}
+
+/// Splits a directive line the way OpenVPN does: on whitespace, honouring
+/// single and double quotes and backslash escapes, stopping at a `#` or `;`
+/// that starts a new token.
+fn tokenize(line: &str) -> Result<Vec<String>, String> {
+    let mut tokens = vec![];
+    let mut token = String::new();
+    let mut in_token = false;
+    let mut quote_char: Option<char> = None;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match quote_char {
+            Some('\'') if c == '\'' => quote_char = None,
+            Some('"') if c == '"' => quote_char = None,
+            Some('"') if c == '\\' => match chars.next() {
+                Some(escaped) => token.push(escaped),
+                None => token.push(c),
+            },
+            Some(_) => token.push(c),
+            None if c.is_whitespace() => {
+                if in_token {
+                    tokens.push(std::mem::take(&mut token));
+                    in_token = false;
+                }
+            }
+            None if !in_token && (c == '#' || c == ';') => break,
+            None if c == '"' || c == '\'' => {
+                quote_char = Some(c);
+                in_token = true;
+            }
+            None if c == '\\' => {
+                in_token = true;
+                match chars.next() {
+                    Some(escaped) => token.push(escaped),
+                    None => token.push(c),
+                }
+            }
+            None => {
+                token.push(c);
+                in_token = true;
+            }
+        }
+    }
+
+    if quote_char.is_some() {
+        return Err("Unterminated quoted string".to_string());
+    }
+
+    if in_token {
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+fn quote(arg: &str) -> String {
+    let needs_quotes = arg.is_empty()
+        || arg.chars().any(|c| {
+            c.is_whitespace() || c == '"' || c == '\'' || c == '\\' || c == '#' || c == ';'
+        });
+
+    if !needs_quotes {
+        return arg.to_string();
+    }
+
+    format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn directive(name: &str, args: &[&str], line: usize) -> Item {
+        Item::Directive(Directive {
+            name: name.to_string(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+            line,
+        })
+    }
+
+    #[test]
+    fn parses_directives() {
+        let (config, diagnostics) = parse("client\n--dev tun\nremote vpn.example.com 1194 udp\n");
+
+        assert!(diagnostics.is_empty());
+        assert_eq!(
+            config.items,
+            vec![
+                directive("client", &[], 1),
+                directive("dev", &["tun"], 2),
+                directive("remote", &["vpn.example.com", "1194", "udp"], 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn honours_quotes_and_escapes() {
+        let (config, diagnostics) =
+            parse("auth-user-pass \"my file\"\nsetenv NAME 'a b' c\\ d \"q\\\"uote\"\n");
+
+        assert!(diagnostics.is_empty());
+        assert_eq!(
+            config.items,
+            vec![
+                directive("auth-user-pass", &["my file"], 1),
+                directive("setenv", &["NAME", "a b", "c d", "q\"uote"], 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_comments_and_blank_lines() {
+        let (config, diagnostics) = parse("# comment\n\n; other\ndev tun # trailing\n");
+
+        assert!(diagnostics.is_empty());
+        assert_eq!(
+            config.items,
+            vec![
+                Item::Comment("# comment".to_string()),
+                Item::Blank,
+                Item::Comment("; other".to_string()),
+                directive("dev", &["tun"], 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_inline_blocks() {
+        let (config, diagnostics) = parse("<ca>\nLINE1\nLINE2\n</ca>\ndev tun\n");
+
+        assert!(diagnostics.is_empty());
+        assert_eq!(
+            config.block("ca"),
+            Some(&InlineBlock {
+                tag: "ca".to_string(),
+                content: "LINE1\nLINE2\n".to_string(),
+                line: 1,
+            })
+        );
+        assert_eq!(config.directive("dev").map(|dev| dev.line), Some(5));
+    }
+
+    #[test]
+    fn writes_back_what_was_read() {
+        let content = "# comment\nclient\n\nremote \"vpn example\" 1194\n<ca>\nCERT\n</ca>\n";
+        let (config, _) = parse(content);
+
+        assert_eq!(config.to_string(), content);
+    }
+
+    #[test]
+    fn reports_unclosed_and_mismatched_blocks() {
+        let (_, diagnostics) = parse("<ca>\nCERT\n</cert>\n");
+        let messages: Vec<String> = diagnostics.iter().map(ToString::to_string).collect();
+
+        assert_eq!(
+            messages,
+            vec![
+                "line 3: </cert> does not close <ca> opened on line 1",
+                "line 1: <ca> is never closed",
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_nested_and_stray_tags() {
+        let (_, diagnostics) = parse("</key>\n<ca>\n<cert>\nCERT\n</ca>\n");
+
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].line, 1);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert_eq!(diagnostics[1].line, 3);
+        assert_eq!(diagnostics[1].severity, Severity::Error);
+    }
+
+    #[test]
+    fn warns_about_empty_and_unknown_blocks() {
+        let (config, diagnostics) = parse("<ca>\n</ca>\n<bogus>\nx\n</bogus>\n");
+
+        assert_eq!(config.blocks().count(), 2);
+        assert_eq!(diagnostics.len(), 2);
+        assert!(diagnostics
+            .iter()
+            .all(|diagnostic| diagnostic.severity == Severity::Warning));
+    }
+
+    #[test]
+    fn reports_unterminated_quotes() {
+        let (config, diagnostics) = parse("remote \"vpn.example.com 1194\n");
+
+        assert!(config.directives().next().is_none());
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].to_string(),
+            "line 1: Unterminated quoted string"
+        );
+    }
+}
@@ -0,0 +1,132 @@
+use std::io;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use super::inline::{self, InlinedFile};
+use super::parser;
+use super::validate::{self, ValidationReport};
+
+/// Configuration content ready to be handed to openvpn3, together with the
+/// outcome of validating it.
+#[derive(Debug, Clone)]
+pub struct PreparedConfig {
+    pub content: String,
+    pub report: ValidationReport,
+}
+
+/// Reads an .ovpn file, inlines the files it references relative to its own
+/// directory and validates the result.
+pub fn prepare_file(path: &Path) -> Result<PreparedConfig> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let base_dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let base_dir = base_dir
+        .canonicalize()
+        .with_context(|| format!("Failed to resolve {}", base_dir.display()))?;
+
+    Ok(prepare_content(&content, |file| {
+        read_within(&base_dir, file)
+    }))
+}
+
+/// Reads a file referenced by a configuration in `base_dir`. Files outside
+/// of it, reached through an absolute path, `..` or a symlink, are refused,
+/// so that importing a configuration cannot send e.g. an SSH key to
+/// openvpn3.
+fn read_within(base_dir: &Path, file: &str) -> io::Result<Vec<u8>> {
+    let path = base_dir.join(file).canonicalize()?;
+    if !path.starts_with(base_dir) {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "the file is outside the directory of the configuration",
+        ));
+    }
+
+    std::fs::read(path)
+}
+
+pub fn prepare_content<F>(content: &str, read: F) -> PreparedConfig
+where
+    F: Fn(&str) -> io::Result<Vec<u8>>,
+{
+    let (mut config, mut diagnostics) = parser::parse(content);
+    let (inlined_files, inline_diagnostics) = inline::inline_external_files(&mut config, read);
+    diagnostics.extend(inline_diagnostics);
+    diagnostics.extend(validate::validate(&config));
+
+    let content = if inlined_files.is_empty() {
+        content.to_string()
+    } else {
+        config.to_string()
+    };
+
+    PreparedConfig {
+        content,
+        report: ValidationReport::new(diagnostics, inlined_files),
+    }
+}
+
+impl PreparedConfig {
+    pub fn inlined_files(&self) -> &[InlinedFile] {
+        &self.report.inlined_files
+    }
+}
+
+/// The name Viscosity stores in a `#viscosity name` comment.
+pub fn embedded_name(content: &str) -> Option<String> {
+    content
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("#viscosity name "))
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+}
+
+/// A name for configuration content that did not come with one: the
+/// embedded name, or else the host of the first remote.
+pub fn suggested_name(content: &str) -> Option<String> {
+    if let Some(name) = embedded_name(content) {
+        return Some(name);
+    }
+
+    let (config, _) = parser::parse(content);
+    config
+        .directive("remote")
+        .and_then(|remote| remote.args.first().cloned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inlines_only_files_next_to_the_config() {
+        let root = std::env::temp_dir().join(format!("konewka-prepare-{}", std::process::id()));
+        let dir = root.join("profile");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("ca.crt"), "CA\n").unwrap();
+        std::fs::write(root.join("outside.key"), "SECRET\n").unwrap();
+
+        let outside = root.join("outside.key");
+        let config = dir.join("client.ovpn");
+        std::fs::write(
+            &config,
+            format!(
+                "client\nca ca.crt\ncert ../outside.key\nkey {}\n",
+                outside.display()
+            ),
+        )
+        .unwrap();
+
+        let prepared = prepare_file(&config).unwrap();
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert!(prepared.content.contains("<ca>\nCA\n</ca>"));
+        assert!(!prepared.content.contains("SECRET"));
+        assert_eq!(prepared.inlined_files().len(), 1);
+        assert!(!prepared.report.valid);
+    }
+}
@@ -0,0 +1,175 @@
+use serde::{Deserialize, Serialize};
+
+use super::parser::{self, OvpnConfig};
+
+const DEFAULT_PORT: u16 = 1194;
+const DEFAULT_PROTO: &str = "udp";
+
+/// A server a configuration can connect to, with the port and protocol
+/// filled in from the defaults of the configuration.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RemoteServer {
+    pub host: String,
+    pub port: u16,
+    /// `udp` or `tcp`
+    pub proto: String,
+}
+
+/// Lists the remotes of a configuration in order, including those of
+/// `<connection>` blocks, without duplicates.
+pub fn list(config: &OvpnConfig) -> Vec<RemoteServer> {
+    let port = default_port(config).unwrap_or(DEFAULT_PORT);
+    let proto = default_proto(config).unwrap_or_else(|| DEFAULT_PROTO.to_string());
+
+    let mut remotes = collect(config, port, &proto);
+    for block in config.blocks().filter(|block| block.tag == "connection") {
+        let (connection, _) = parser::parse(&block.content);
+        let connection_port = default_port(&connection).unwrap_or(port);
+        let connection_proto = default_proto(&connection).unwrap_or_else(|| proto.clone());
+        remotes.extend(collect(&connection, connection_port, &connection_proto));
+    }
+
+    let mut unique: Vec<RemoteServer> = vec![];
+    for remote in remotes {
+        if !unique.contains(&remote) {
+            unique.push(remote);
+        }
+    }
+
+    unique
+}
+
+fn collect(config: &OvpnConfig, default_port: u16, default_proto: &str) -> Vec<RemoteServer> {
+    config
+        .directives()
+        .filter(|directive| directive.name == "remote" && !directive.args.is_empty())
+        .map(|directive| RemoteServer {
+            host: directive.args[0].clone(),
+            port: directive
+                .args
+                .get(1)
+                .and_then(|port| port.parse().ok())
+                .unwrap_or(default_port),
+            proto: directive
+                .args
+                .get(2)
+                .map(|proto| normalize_proto(proto))
+                .unwrap_or_else(|| default_proto.to_string()),
+        })
+        .collect()
+}
+
+/// Both `port` and `rport` set the remote port, OpenVPN keeps the one
+/// given last.
+fn default_port(config: &OvpnConfig) -> Option<u16> {
+    config
+        .directives()
+        .filter(|directive| matches!(directive.name.as_str(), "port" | "rport"))
+        .last()
+        .and_then(|directive| directive.args.first())
+        .and_then(|port| port.parse().ok())
+}
+
+fn default_proto(config: &OvpnConfig) -> Option<String> {
+    config
+        .directive("proto")
+        .and_then(|directive| directive.args.first())
+        .map(|proto| normalize_proto(proto))
+}
+
+/// Reduces protocol variants such as `udp6` or `tcp-client` to the `udp`
+/// and `tcp` that openvpn3's `proto-override` accepts.
+fn normalize_proto(proto: &str) -> String {
+    if proto.to_lowercase().starts_with("tcp") {
+        "tcp".to_string()
+    } else {
+        "udp".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn remote(host: &str, port: u16, proto: &str) -> RemoteServer {
+        RemoteServer {
+            host: host.to_string(),
+            port,
+            proto: proto.to_string(),
+        }
+    }
+
+    fn remotes(content: &str) -> Vec<RemoteServer> {
+        let (config, _) = parser::parse(content);
+        list(&config)
+    }
+
+    #[test]
+    fn fills_in_defaults() {
+        assert_eq!(
+            remotes("remote a.example.com\nremote b.example.com 443 tcp-client\n"),
+            vec![
+                remote("a.example.com", 1194, "udp"),
+                remote("b.example.com", 443, "tcp"),
+            ]
+        );
+    }
+
+    #[test]
+    fn uses_the_port_and_proto_of_the_config() {
+        assert_eq!(
+            remotes("proto tcp6\nport 443\nremote a.example.com\nremote b.example.com 1194 udp6\n"),
+            vec![
+                remote("a.example.com", 443, "tcp"),
+                remote("b.example.com", 1194, "udp"),
+            ]
+        );
+        assert_eq!(
+            remotes("rport 8443\nremote a.example.com\n"),
+            vec![remote("a.example.com", 8443, "udp")]
+        );
+    }
+
+    #[test]
+    fn uses_the_port_given_last() {
+        assert_eq!(
+            remotes("port 1195\nrport 8443\nremote a.example.com\n"),
+            vec![remote("a.example.com", 8443, "udp")]
+        );
+        assert_eq!(
+            remotes("rport 8443\nport 1195\nremote a.example.com\n"),
+            vec![remote("a.example.com", 1195, "udp")]
+        );
+    }
+
+    #[test]
+    fn includes_connection_blocks() {
+        let content = "proto tcp\n\
+                       remote a.example.com 443\n\
+                       <connection>\n\
+                       remote b.example.com\n\
+                       </connection>\n\
+                       <connection>\n\
+                       remote c.example.com 1194\n\
+                       proto udp\n\
+                       </connection>\n";
+
+        assert_eq!(
+            remotes(content),
+            vec![
+                remote("a.example.com", 443, "tcp"),
+                remote("b.example.com", 1194, "tcp"),
+                remote("c.example.com", 1194, "udp"),
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_duplicates_and_empty_remotes() {
+        assert_eq!(
+            remotes("remote\nremote a.example.com\nremote a.example.com 1194 udp\n"),
+            vec![remote("a.example.com", 1194, "udp")]
+        );
+        assert!(remotes("client\ndev tun\n").is_empty());
+    }
+}
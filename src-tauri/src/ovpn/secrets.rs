@@ -0,0 +1,36 @@
+use super::parser::{Item, OvpnConfig};
+
+/// Inline blocks holding material that is personal to the user or shared
+/// only among the clients of a server: private keys, static TLS keys and
+/// stored credentials.
+pub const PRIVATE_TAGS: [&str; 8] = [
+    "key",
+    "pkcs12",
+    "secret",
+    "tls-auth",
+    "tls-crypt",
+    "tls-crypt-v2",
+    "auth-user-pass",
+    "http-proxy-user-pass",
+];
+
+pub fn is_private(tag: &str) -> bool {
+    PRIVATE_TAGS.contains(&tag)
+}
+
+/// Removes private inline blocks, leaving a comment in place of each so the
+/// recipient knows what to add back. Returns the tags that were removed.
+pub fn strip_private_keys(config: &mut OvpnConfig) -> Vec<String> {
+    let mut removed = vec![];
+
+    for item in config.items.iter_mut() {
+        if let Item::Block(block) = item {
+            if is_private(&block.tag) {
+                removed.push(block.tag.clone());
+                *item = Item::Comment(format!("# <{}> removed on export", block.tag));
+            }
+        }
+    }
+
+    removed
+}
@@ -0,0 +1,190 @@
+use serde::Serialize;
+
+use super::parser::{self, OvpnConfig};
+use super::secrets;
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Remote {
+    pub host: String,
+    pub port: Option<String>,
+    pub proto: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthMethod {
+    Certificate,
+    UserPass,
+    CertificateAndUserPass,
+    Pkcs12,
+    StaticKey,
+    Unknown,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CipherSettings {
+    pub cipher: Option<String>,
+    pub data_ciphers: Option<String>,
+    pub data_ciphers_fallback: Option<String>,
+    pub auth: Option<String>,
+    pub tls_cipher: Option<String>,
+    pub tls_version_min: Option<String>,
+    pub tls_version_max: Option<String>,
+    /// tls-auth, tls-crypt or tls-crypt-v2, whichever protects the control
+    /// channel
+    pub tls_protection: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockSummary {
+    pub tag: String,
+    pub line: usize,
+    pub lines: usize,
+    /// Private keys and credentials are never returned
+    pub masked: bool,
+    pub content: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigSummary {
+    pub remotes: Vec<Remote>,
+    pub proto: Option<String>,
+    pub port: Option<String>,
+    pub dev: Option<String>,
+    pub compression: Option<String>,
+    pub auth_method: AuthMethod,
+    pub ciphers: CipherSettings,
+    pub blocks: Vec<BlockSummary>,
+}
+
+/// Describes a configuration for display. Remotes inside `<connection>`
+/// blocks are included alongside the top-level ones.
+pub fn summarize(config: &OvpnConfig) -> ConfigSummary {
+    let arg = |name: &str| {
+        config
+            .directive(name)
+            .and_then(|directive| directive.args.first().cloned())
+    };
+    let has = |name: &str| config.directive(name).is_some() || config.block(name).is_some();
+
+    let mut remotes = collect_remotes(config);
+    for block in config.blocks().filter(|block| block.tag == "connection") {
+        let (connection, _) = parser::parse(&block.content);
+        remotes.extend(collect_remotes(&connection));
+    }
+
+    let auth_method = match (has("cert") && has("key"), has("auth-user-pass")) {
+        (true, true) => AuthMethod::CertificateAndUserPass,
+        (true, false) => AuthMethod::Certificate,
+        (false, true) => AuthMethod::UserPass,
+        (false, false) if has("pkcs12") => AuthMethod::Pkcs12,
+        (false, false) if has("secret") => AuthMethod::StaticKey,
+        (false, false) => AuthMethod::Unknown,
+    };
+
+    let tls_protection = ["tls-crypt-v2", "tls-crypt", "tls-auth"]
+        .into_iter()
+        .find(|name| has(name))
+        .map(str::to_string);
+
+    // Without an algorithm, compress only enables framing ("stub")
+    let compression = match config.directive("compress") {
+        Some(_) => arg("compress").or_else(|| Some("stub".to_string())),
+        None => arg("comp-lzo").or_else(|| has("comp-lzo").then(|| "lzo".to_string())),
+    };
+
+    let blocks = config
+        .blocks()
+        .filter(|block| block.tag != "connection")
+        .map(|block| {
+            let masked = secrets::is_private(&block.tag);
+            BlockSummary {
+                tag: block.tag.clone(),
+                line: block.line,
+                lines: block.content.lines().count(),
+                masked,
+                content: (!masked).then(|| block.content.clone()),
+            }
+        })
+        .collect();
+
+    ConfigSummary {
+        remotes,
+        proto: arg("proto"),
+        // Both set the remote port, the one given last wins
+        port: config
+            .directives()
+            .filter(|directive| matches!(directive.name.as_str(), "port" | "rport"))
+            .last()
+            .and_then(|directive| directive.args.first().cloned()),
+        dev: arg("dev"),
+        compression,
+        auth_method,
+        ciphers: CipherSettings {
+            cipher: arg("cipher"),
+            data_ciphers: arg("data-ciphers").or_else(|| arg("ncp-ciphers")),
+            data_ciphers_fallback: arg("data-ciphers-fallback"),
+            auth: arg("auth"),
+            tls_cipher: arg("tls-cipher"),
+            tls_version_min: arg("tls-version-min"),
+            tls_version_max: arg("tls-version-max"),
+            tls_protection,
+        },
+        blocks,
+    }
+}
+
+fn collect_remotes(config: &OvpnConfig) -> Vec<Remote> {
+    config
+        .directives()
+        .filter(|directive| directive.name == "remote" && !directive.args.is_empty())
+        .map(|directive| Remote {
+            host: directive.args[0].clone(),
+            port: directive.args.get(1).cloned(),
+            proto: directive.args.get(2).cloned(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn masks_private_blocks() {
+        let mut content = String::from("client\nremote vpn.example.com 1194\n");
+        for tag in ["ca", "cert"].iter().chain(secrets::PRIVATE_TAGS.iter()) {
+            content.push_str(&format!("<{0}>\ncontent of {0}\n</{0}>\n", tag));
+        }
+        let (config, _) = parser::parse(&content);
+
+        let summary = summarize(&config);
+
+        for block in summary.blocks.iter() {
+            let private = secrets::PRIVATE_TAGS.contains(&block.tag.as_str());
+            assert_eq!(block.masked, private, "<{}>", block.tag);
+            assert_eq!(block.content.is_none(), private, "<{}>", block.tag);
+        }
+        for tag in ["secret", "tls-auth", "tls-crypt", "tls-crypt-v2", "key"] {
+            let block = summary.blocks.iter().find(|block| block.tag == tag);
+            assert!(block.is_some_and(|block| block.masked), "<{}>", tag);
+        }
+        assert_eq!(
+            summary.blocks.len(),
+            secrets::PRIVATE_TAGS.len() + 2,
+            "every block is listed"
+        );
+    }
+
+    #[test]
+    fn reports_the_port_given_last() {
+        let (config, _) = parser::parse("port 1195\nrport 8443\nremote vpn.example.com\n");
+        assert_eq!(summarize(&config).port.as_deref(), Some("8443"));
+
+        let (config, _) = parser::parse("rport 8443\nport 1195\nremote vpn.example.com\n");
+        assert_eq!(summarize(&config).port.as_deref(), Some("1195"));
+    }
+}
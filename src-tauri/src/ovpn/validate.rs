@@ -0,0 +1,202 @@
+use serde::Serialize;
+
+use super::inline::InlinedFile;
+use super::parser::{Diagnostic, OvpnConfig, Severity};
+
+const PROTOCOLS: [&str; 7] = ["udp", "udp4", "udp6", "tcp", "tcp4", "tcp6", "tcp-client"];
+
+/// Directives that only make sense for the classic OpenVPN 2 daemon and are
+/// ignored or rejected by openvpn3.
+const UNSUPPORTED_DIRECTIVES: [&str; 14] = [
+    "chroot",
+    "daemon",
+    "down",
+    "group",
+    "log",
+    "log-append",
+    "management",
+    "plugin",
+    "route-up",
+    "script-security",
+    "status",
+    "up",
+    "user",
+    "writepid",
+];
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ValidationReport {
+    pub valid: bool,
+    pub diagnostics: Vec<Diagnostic>,
+    /// Files referenced by the configuration that were pulled in as inline
+    /// blocks
+    pub inlined_files: Vec<InlinedFile>,
+}
+
+impl ValidationReport {
+    pub fn new(mut diagnostics: Vec<Diagnostic>, inlined_files: Vec<InlinedFile>) -> Self {
+        diagnostics.sort_by_key(|diagnostic| diagnostic.line);
+
+        let valid = !diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.severity == Severity::Error);
+
+        Self {
+            valid,
+            diagnostics,
+            inlined_files,
+        }
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Error)
+    }
+
+    /// One line per error, suitable for showing in place of a generic
+    /// import failure.
+    pub fn error_summary(&self) -> String {
+        self.errors()
+            .map(|error| error.to_string())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+pub fn validate(config: &OvpnConfig) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+
+    for directive in config.directives() {
+        let line = directive.line;
+        let args = &directive.args;
+
+        match directive.name.as_str() {
+            "remote" => {
+                if args.is_empty() {
+                    diagnostics.push(Diagnostic::error(line, "remote requires a host"));
+                }
+                if let Some(port) = args.get(1) {
+                    check_port(&mut diagnostics, line, port);
+                }
+                if let Some(proto) = args.get(2) {
+                    check_protocol(&mut diagnostics, line, proto);
+                }
+            }
+            "port" | "rport" | "lport" => match args.first() {
+                Some(port) => check_port(&mut diagnostics, line, port),
+                None => diagnostics.push(Diagnostic::error(
+                    line,
+                    format!("{} requires a port number", directive.name),
+                )),
+            },
+            "proto" => match args.first() {
+                Some(proto) => check_protocol(&mut diagnostics, line, proto),
+                None => diagnostics.push(Diagnostic::error(line, "proto requires a protocol")),
+            },
+            "key-direction" if !matches!(args.first().map(String::as_str), Some("0" | "1")) => {
+                diagnostics.push(Diagnostic::error(line, "key-direction must be 0 or 1"));
+            }
+            "ca" | "cert" | "key" | "extra-certs" | "tls-auth" | "tls-crypt" | "tls-crypt-v2"
+            | "secret" | "pkcs12" | "dh" | "crl-verify"
+                if args.first().is_some_and(|file| file != "[inline]") =>
+            {
+                diagnostics.push(Diagnostic::warning(
+                    line,
+                    format!(
+                        "{} refers to the external file {}, which openvpn3 cannot read after import",
+                        directive.name, args[0]
+                    ),
+                ));
+            }
+            name if UNSUPPORTED_DIRECTIVES.contains(&name) => {
+                diagnostics.push(Diagnostic::warning(
+                    line,
+                    format!("{} is not supported by openvpn3 and will be ignored", name),
+                ));
+            }
+            _ => (),
+        }
+    }
+
+    let mut seen_tags: Vec<&str> = vec![];
+    for block in config.blocks() {
+        if block.tag != "connection" && seen_tags.contains(&block.tag.as_str()) {
+            diagnostics.push(Diagnostic::warning(
+                block.line,
+                format!("<{}> is defined more than once", block.tag),
+            ));
+        }
+        seen_tags.push(&block.tag);
+
+        let expected_marker = match block.tag.as_str() {
+            "ca" | "cert" | "extra-certs" => Some("-----BEGIN CERTIFICATE-----"),
+            "key" => Some("PRIVATE KEY-----"),
+            "tls-auth" | "tls-crypt" | "secret" => Some("-----BEGIN OpenVPN Static key V1-----"),
+            "tls-crypt-v2" => Some("-----BEGIN OpenVPN tls-crypt-v2 client key-----"),
+            _ => None,
+        };
+
+        if let Some(marker) = expected_marker {
+            if !block.content.trim().is_empty() && !block.content.contains(marker) {
+                diagnostics.push(Diagnostic::error(
+                    block.line,
+                    format!("<{}> does not contain \"{}\"", block.tag, marker),
+                ));
+            }
+        }
+    }
+
+    let has_remote = config.directive("remote").is_some() || config.block("connection").is_some();
+    if !has_remote {
+        diagnostics.push(Diagnostic::error(0, "No remote server is defined"));
+    }
+
+    if config.directive("client").is_none() && config.directive("tls-client").is_none() {
+        diagnostics.push(Diagnostic::warning(
+            0,
+            "Neither client nor tls-client is set, openvpn3 only supports client mode",
+        ));
+    }
+
+    let has = |name: &str| config.directive(name).is_some() || config.block(name).is_some();
+
+    if !has("ca") && !has("pkcs12") && !has("secret") && !has("peer-fingerprint") {
+        diagnostics.push(Diagnostic::warning(0, "No CA certificate is defined"));
+    }
+
+    if has("cert") && !has("key") && config.directive("management-external-key").is_none() {
+        diagnostics.push(Diagnostic::error(
+            0,
+            "A client certificate is defined without a key",
+        ));
+    }
+
+    if has("key") && !has("cert") {
+        diagnostics.push(Diagnostic::error(
+            0,
+            "A private key is defined without a certificate",
+        ));
+    }
+
+    diagnostics
+}
+
+fn check_port(diagnostics: &mut Vec<Diagnostic>, line: usize, port: &str) {
+    match port.parse::<u16>() {
+        Ok(port) if port > 0 => (),
+        _ => diagnostics.push(Diagnostic::error(
+            line,
+            format!("{} is not a valid port number", port),
+        )),
+    }
+}
+
+fn check_protocol(diagnostics: &mut Vec<Diagnostic>, line: usize, proto: &str) {
+    if !PROTOCOLS.contains(&proto) {
+        diagnostics.push(Diagnostic::error(
+            line,
+            format!("{} is not a valid protocol", proto),
+        ));
+    }
+}
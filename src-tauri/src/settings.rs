@@ -0,0 +1,194 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::connection::failover::FailoverGroup;
+use crate::connection::fallback::ProtocolFallback;
+use crate::connection::sets::ConnectionSet;
+use crate::dbus::structs::SignalScope;
+use crate::import::server::ProfileType;
+use crate::ovpn::remotes::RemoteServer;
+
+/// Per-configuration preferences, keyed by the configuration's D-Bus path.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct ConfigSettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_verbosity: Option<u32>,
+    /// The file the configuration was imported from, if it is known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<ConfigSource>,
+    /// SHA-256 of the content handed to openvpn3, whichever way the
+    /// configuration was imported
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_hash: Option<String>,
+    /// The profile server the configuration was downloaded from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server: Option<ServerSource>,
+    /// The remote last chosen to connect to, unset to let openvpn3 choose
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote: Option<RemoteServer>,
+    /// Probe the remotes before connecting and pick the fastest one
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub auto_select_remote: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub protocol_fallback: Option<ProtocolFallback>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ConfigSource {
+    pub file: String,
+    /// SHA-256 of the file content at the time of the import
+    pub hash: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerSource {
+    pub url: String,
+    pub username: String,
+    pub profile_type: ProfileType,
+    /// SHA-256 of the profile as last downloaded
+    pub hash: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct Settings {
+    #[serde(default)]
+    pub configs: HashMap<String, ConfigSettings>,
+    #[serde(default)]
+    pub signal_scope: SignalScope,
+    #[serde(default)]
+    pub watched_directories: Vec<WatchedDirectory>,
+    #[serde(default)]
+    pub failover_groups: Vec<FailoverGroup>,
+    #[serde(default)]
+    pub connection_sets: Vec<ConnectionSet>,
+}
+
+impl Settings {
+    /// Points everything that refers to `config_path` at `new_path`, for
+    /// when a configuration is imported again.
+    pub fn move_config(&mut self, config_path: &str, new_path: &str) {
+        if let Some(config) = self.configs.remove(config_path) {
+            self.configs.insert(new_path.to_string(), config);
+        }
+
+        let members = self
+            .failover_groups
+            .iter_mut()
+            .flat_map(|group| group.configs.iter_mut())
+            .chain(
+                self.connection_sets
+                    .iter_mut()
+                    .flat_map(|set| set.configs.iter_mut()),
+            );
+        for member in members {
+            if member == config_path {
+                *member = new_path.to_string();
+            }
+        }
+    }
+}
+
+/// A directory whose configurations are kept imported, see
+/// `import::watch`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchedDirectory {
+    pub path: String,
+    #[serde(default)]
+    pub recursive: bool,
+    /// Remove configurations whose file was deleted from the directory
+    #[serde(default)]
+    pub remove_missing: bool,
+}
+
+/// Konewka's own settings, persisted as JSON in
+/// `$XDG_CONFIG_HOME/konewka/settings.json`.
+pub struct SettingsStore {
+    path: Option<PathBuf>,
+    settings: Mutex<Settings>,
+}
+
+impl SettingsStore {
+    pub fn load() -> Self {
+        let path =
+            tauri::api::path::config_dir().map(|dir| dir.join("konewka").join("settings.json"));
+
+        let settings = match &path {
+            Some(path) if path.exists() => match read_settings(path) {
+                Ok(settings) => settings,
+                Err(error) => {
+                    tracing::warn!("Failed to read settings, using defaults: {:#}", error);
+                    Settings::default()
+                }
+            },
+            _ => Settings::default(),
+        };
+
+        Self {
+            path,
+            settings: Mutex::new(settings),
+        }
+    }
+
+    pub fn get(&self) -> Settings {
+        self.settings.lock().unwrap().clone()
+    }
+
+    pub fn config(&self, config_path: &str) -> ConfigSettings {
+        let settings = self.settings.lock().unwrap();
+        settings
+            .configs
+            .get(config_path)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Applies `change` and writes the result to disk. The settings in
+    /// memory are left as they were when writing fails.
+    pub fn update<F>(&self, change: F) -> Result<()>
+    where
+        F: FnOnce(&mut Settings),
+    {
+        let mut settings = self.settings.lock().unwrap();
+        let mut updated = settings.clone();
+        change(&mut updated);
+
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Err(anyhow::anyhow!("No configuration directory available")),
+        };
+
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).with_context(|| "Failed to create settings directory")?;
+        }
+
+        let content = serde_json::to_string_pretty(&updated)
+            .with_context(|| "Failed to serialize settings")?;
+        std::fs::write(path, content).with_context(|| "Failed to write settings")?;
+
+        *settings = updated;
+        Ok(())
+    }
+
+    pub fn update_config<F>(&self, config_path: &str, change: F) -> Result<()>
+    where
+        F: FnOnce(&mut ConfigSettings),
+    {
+        self.update(|settings| {
+            let config = settings.configs.entry(config_path.to_string()).or_default();
+            change(config);
+        })
+    }
+}
+
+fn read_settings(path: &Path) -> Result<Settings> {
+    let content = std::fs::read_to_string(path).with_context(|| "Failed to read settings")?;
+    let settings = serde_json::from_str(&content).with_context(|| "Failed to parse settings")?;
+
+    Ok(settings)
+}
@@ -1,5 +1,16 @@
 use serde::{Deserialize, Serialize};
 
+use crate::connection::{
+    failover::{ActiveGroup, FailoverGroup},
+    fallback::ProtocolFallback,
+};
+use crate::dbus::overrides::OverrideValue;
+use crate::import::server::ProfileType;
+use crate::logs::export::LogExportFormat;
+use crate::ovpn::{
+    networkmanager::UnmappedOption, remotes::RemoteServer, validate::ValidationReport,
+};
+
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct ImportConfigPayload {
@@ -9,6 +20,32 @@ pub struct ImportConfigPayload {
     pub config_file: String,
 }
 
+/// The configurations imported from a file or bundle, and the profiles of
+/// the bundle that openvpn3 did not accept.
+#[derive(Serialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportConfigResult {
+    pub config_paths: Vec<String>,
+    pub failed: Vec<ImportFailure>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ImportFailure {
+    /// Location of the profile inside the bundle
+    pub source: String,
+    pub error: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportConfigContentPayload {
+    /// Derived from the content when empty
+    pub config_name: String,
+    pub single_use: bool,
+    pub persistent: bool,
+    pub config_content: String,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct LogMessage {
     pub path: String,
@@ -16,4 +53,202 @@ pub struct LogMessage {
     pub first_flag: u32,
     pub second_flag: u32,
     pub message: String,
+    #[serde(default)]
+    pub config_path: Option<String>,
+    #[serde(default)]
+    pub config_name: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportLogsPayload {
+    pub session_path: Option<String>,
+    pub format: LogExportFormat,
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+    pub file: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionLogVerbosityPayload {
+    pub session_path: String,
+    pub log_verbosity: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionLogForwardPayload {
+    pub session_path: String,
+    pub enabled: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigLogVerbosityPayload {
+    pub config_path: String,
+    pub log_verbosity: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportNetworkManagerPayload {
+    /// Path of a .nmconnection keyfile
+    pub keyfile: Option<String>,
+    /// Object path of a connection stored by NetworkManager, used when no
+    /// keyfile is given
+    pub connection_path: Option<String>,
+    /// Defaults to the id of the NetworkManager connection
+    pub config_name: Option<String>,
+    pub single_use: bool,
+    pub persistent: bool,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkManagerImportResult {
+    pub config_path: String,
+    pub report: ValidationReport,
+    pub unmapped: Vec<UnmappedOption>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DirectoryImportPayload {
+    pub directory: String,
+    #[serde(default)]
+    pub recursive: bool,
+    #[serde(default)]
+    pub single_use: bool,
+    #[serde(default)]
+    pub persistent: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerLoginPayload {
+    pub url: String,
+    pub username: String,
+    pub password: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerImportPayload {
+    pub url: String,
+    pub username: String,
+    pub password: String,
+    pub profile_type: ProfileType,
+    /// Derived from the profile when empty
+    #[serde(default)]
+    pub config_name: String,
+    #[serde(default)]
+    pub single_use: bool,
+    #[serde(default)]
+    pub persistent: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerRefreshPayload {
+    pub config_path: String,
+    pub password: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerRefreshResult {
+    /// Path of the configuration after the refresh, which changes when the
+    /// profile was updated
+    pub config_path: String,
+    pub updated: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RenameConfigPayload {
+    pub config_path: String,
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigOverridePayload {
+    pub config_path: String,
+    pub name: String,
+    /// Unsets the override when missing
+    pub value: Option<OverrideValue>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectRemotePayload {
+    pub config_path: String,
+    /// Lets openvpn3 choose when missing
+    pub remote: Option<RemoteServer>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigRemotes {
+    pub remotes: Vec<RemoteServer>,
+    /// The remote chosen last time, if it is still in the configuration
+    pub selected: Option<RemoteServer>,
+    pub auto_select: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigUserPayload {
+    pub config_path: String,
+    /// A user name or a UID
+    pub user: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigFlagPayload {
+    pub config_path: String,
+    pub enabled: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SealConfigPayload {
+    pub config_path: String,
+    /// Sealing cannot be undone, so the caller has to say it means it
+    #[serde(default)]
+    pub confirm: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigAutoSelectPayload {
+    pub config_path: String,
+    pub enabled: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ProtocolFallbackPayload {
+    pub config_path: String,
+    /// Disables the fallback when missing
+    pub fallback: Option<ProtocolFallback>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FailoverGroupStatus {
+    #[serde(flatten)]
+    pub group: FailoverGroup,
+    pub active: Option<ActiveGroup>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportConfigPayload {
+    pub config_path: String,
+    pub file: String,
+    #[serde(default)]
+    pub strip_private_keys: bool,
 }
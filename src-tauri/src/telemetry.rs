@@ -0,0 +1,78 @@
+use std::path::PathBuf;
+
+use tracing_appender::{
+    non_blocking::WorkerGuard,
+    rolling::{RollingFileAppender, Rotation},
+};
+use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+
+const LOG_ENV: &str = "KONEWKA_LOG";
+const DEFAULT_FILTER: &str = "info";
+const MAX_LOG_FILES: usize = 7;
+
+/// Sets up logging to stderr and to a daily rotated file in
+/// `$XDG_DATA_HOME/konewka/logs`.
+///
+/// Verbosity is taken from `--log-level <filter>` (or `-v` for debug), then
+/// from the `KONEWKA_LOG` environment variable, using `EnvFilter` syntax,
+/// e.g. `konewka=debug`. The returned guard flushes the file on drop and has
+/// to be kept alive for the lifetime of the app.
+pub fn init() -> Option<WorkerGuard> {
+    let filter = filter_from_args()
+        .or_else(|| std::env::var(LOG_ENV).ok())
+        .unwrap_or_else(|| DEFAULT_FILTER.to_string());
+
+    let filter = EnvFilter::try_new(&filter).unwrap_or_else(|error| {
+        eprintln!("Invalid log filter \"{}\": {}", filter, error);
+        EnvFilter::new(DEFAULT_FILTER)
+    });
+
+    let file_appender = log_dir().and_then(|dir| {
+        RollingFileAppender::builder()
+            .rotation(Rotation::DAILY)
+            .filename_prefix("konewka")
+            .filename_suffix("log")
+            .max_log_files(MAX_LOG_FILES)
+            .build(dir)
+            .ok()
+    });
+
+    let (file_layer, guard) = match file_appender {
+        Some(appender) => {
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            let layer = fmt::layer().with_ansi(false).with_writer(writer);
+            (Some(layer), Some(guard))
+        }
+        None => (None, None),
+    };
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer().with_writer(std::io::stderr))
+        .with(file_layer)
+        .init();
+
+    guard
+}
+
+pub fn log_dir() -> Option<PathBuf> {
+    tauri::api::path::data_local_dir().map(|dir| dir.join("konewka").join("logs"))
+}
+
+fn filter_from_args() -> Option<String> {
+    let mut args = std::env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        if arg == "-v" || arg == "--verbose" {
+            return Some("debug".to_string());
+        }
+        if arg == "--log-level" {
+            return args.next();
+        }
+        if let Some(filter) = arg.strip_prefix("--log-level=") {
+            return Some(filter.to_string());
+        }
+    }
+
+    None
+}
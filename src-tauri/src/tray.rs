@@ -0,0 +1,96 @@
+use tauri::{
+    AppHandle, CustomMenuItem, Manager, SystemTrayMenu, SystemTrayMenuItem, SystemTraySubmenu,
+};
+
+use crate::connection::sets::{self, ConnectionSet};
+use crate::MyState;
+
+const CONNECT_SET: &str = "connect_set:";
+const DISCONNECT_SET: &str = "disconnect_set:";
+
+/// The tray menu, with a submenu to connect and disconnect each connection
+/// set.
+pub fn menu(connection_sets: &[ConnectionSet]) -> SystemTrayMenu {
+    let mut menu = SystemTrayMenu::new().add_item(CustomMenuItem::new("show", "Show"));
+
+    if !connection_sets.is_empty() {
+        menu = menu.add_native_item(SystemTrayMenuItem::Separator);
+    }
+
+    for set in connection_sets {
+        let items = SystemTrayMenu::new()
+            .add_item(CustomMenuItem::new(
+                format!("{}{}", CONNECT_SET, set.name),
+                "Connect",
+            ))
+            .add_item(CustomMenuItem::new(
+                format!("{}{}", DISCONNECT_SET, set.name),
+                "Disconnect",
+            ));
+        menu = menu.add_submenu(SystemTraySubmenu::new(set.name.clone(), items));
+    }
+
+    menu
+}
+
+/// Rebuilds the tray menu after the connection sets changed.
+pub fn refresh(app: &AppHandle, connection_sets: &[ConnectionSet]) {
+    if let Err(error) = app.tray_handle().set_menu(menu(connection_sets)) {
+        tracing::error!("Failed to update the tray menu: {:?}", error);
+    }
+}
+
+pub fn on_menu_item_click(app: &AppHandle, id: &str) {
+    if id == "show" {
+        let window = app.get_window("main").unwrap();
+        window.show().unwrap();
+        return;
+    }
+
+    let state = app.state::<MyState>();
+    let openvpn3 = state.openvpn3.clone();
+    let settings = state.settings.clone();
+    let failover = state.failover.clone();
+    let app = app.clone();
+
+    if let Some(name) = id.strip_prefix(CONNECT_SET) {
+        let name = name.to_string();
+
+        tokio::spawn(async move {
+            let result = match sets::find(&settings, &name) {
+                Ok(set) => sets::connect(&openvpn3, &settings, &set).await,
+                Err(error) => Err(error),
+            };
+
+            match result {
+                Ok(report) => {
+                    if let Err(error) = app.emit_all("connection_set", report) {
+                        tracing::error!("Failed to emit connection_set: {:?}", error);
+                    }
+                }
+                Err(error) => tracing::error!("Failed to connect {}: {:#}", name, error),
+            }
+        });
+    } else if let Some(name) = id.strip_prefix(DISCONNECT_SET) {
+        let name = name.to_string();
+
+        tokio::spawn(async move {
+            let result = match sets::find(&settings, &name) {
+                Ok(set) => sets::disconnect(&openvpn3, &set).await,
+                Err(error) => Err(error),
+            };
+
+            match result {
+                Ok(disconnected) => {
+                    for session_path in disconnected.iter() {
+                        failover.release(session_path);
+                    }
+                    if let Err(error) = app.emit_all("connection_set_disconnected", name) {
+                        tracing::error!("Failed to emit connection_set_disconnected: {:?}", error);
+                    }
+                }
+                Err(error) => tracing::error!("Failed to disconnect {}: {:#}", name, error),
+            }
+        });
+    }
+}
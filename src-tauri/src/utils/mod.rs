@@ -1,2 +1,2 @@
 pub mod utils;
-pub use utils::async_retry;
+pub use utils::{async_retry, current_uid, hostname};
@@ -1,4 +1,4 @@
-use std::{pin::Pin, time::Duration};
+use std::{os::unix::fs::MetadataExt, pin::Pin, time::Duration};
 
 use futures::Future;
 use tokio::time::sleep;
@@ -15,14 +15,14 @@ where
                 return Ok(r);
             }
             Err(e) => {
-                eprintln!("Error: {}", e);
                 retries += 1;
 
                 if retries > max_retries {
+                    tracing::error!(error = %e, retries, "Giving up after retries");
                     return Err(e);
                 }
 
-                eprintln!("Error, retrying in 2 seconds");
+                tracing::warn!(error = %e, retries, "Retrying in 2 seconds");
                 sleep(Duration::from_secs(2)).await;
 
                 continue;
@@ -30,3 +30,16 @@ where
         }
     }
 }
+
+pub fn hostname() -> String {
+    match std::fs::read_to_string("/etc/hostname") {
+        Ok(hostname) if !hostname.trim().is_empty() => hostname.trim().to_string(),
+        _ => "-".to_string(),
+    }
+}
+
+pub fn current_uid() -> Option<u32> {
+    std::fs::metadata("/proc/self")
+        .map(|metadata| metadata.uid())
+        .ok()
+}
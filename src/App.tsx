@@ -26,12 +26,16 @@ import { SubmitHandler, useForm } from "react-hook-form";
 import { AddIcon, DeleteIcon, LinkIcon } from "@chakra-ui/icons";
 import {
   getConfigurationPathToImport,
+  getImportError,
   invokeRemoveConfiguration,
   invokeImportConfiguration,
   invokeSelectFile,
   invokeNewTunnel,
   invokeDisconnectSession,
   invokeConnectSession,
+  getConnectionSets,
+  invokeConnectConnectionSet,
+  invokeDisconnectConnectionSet,
 } from "./store/features/common/common";
 import { useEffect } from "react";
 import {
@@ -46,6 +50,7 @@ import { LogsContainer } from "./components/Logs";
 const ImportConfigurationForm = () => {
   const dispatch = useDispatch();
   const configurationPathToImport = useSelector(getConfigurationPathToImport);
+  const importError = useSelector(getImportError);
   const {
     register,
     handleSubmit,
@@ -92,6 +97,12 @@ const ImportConfigurationForm = () => {
           )}
         </FormControl>
 
+        {importError && (
+          <Text color="red.500" whiteSpace="pre-wrap">
+            {importError}
+          </Text>
+        )}
+
         <Button type="submit">Import</Button>
       </Stack>
     </form>
@@ -275,6 +286,64 @@ const SessionsList = () => {
   );
 };
 
+const ConnectionSetsList = () => {
+  const connectionSets = useSelector(getConnectionSets);
+  const configs = useSelector(getAllConfigs);
+  const dispatch = useDispatch();
+
+  const configName = (path: string) =>
+    configs.find((config) => config.path === path)?.name ?? path;
+
+  return (
+    <TableContainer>
+      <Table layout={"fixed"} size="sm" variant="simple">
+        <TableCaption>Connection sets</TableCaption>
+        <Thead>
+          <Tr>
+            <Th w={200}>Name</Th>
+            <Th>Configurations</Th>
+            <Th w={100}>Actions</Th>
+          </Tr>
+        </Thead>
+        <Tbody>
+          {connectionSets.map((set) => (
+            <Tr key={set.name}>
+              <Td>{set.name}</Td>
+              <Td>
+                <Text
+                  overflow="hidden"
+                  textOverflow={"ellipsis"}
+                  whiteSpace={"nowrap"}
+                >
+                  {set.configs.map(configName).join(", ")}
+                </Text>
+              </Td>
+              <Td>
+                <HStack spacing="6px">
+                  <IconButton
+                    onClick={() => {
+                      dispatch(invokeConnectConnectionSet(set.name));
+                    }}
+                    aria-label="Connect set"
+                    icon={<LinkIcon />}
+                  />
+                  <IconButton
+                    onClick={() => {
+                      dispatch(invokeDisconnectConnectionSet(set.name));
+                    }}
+                    aria-label="Disconnect set"
+                    icon={<DeleteIcon />}
+                  />
+                </HStack>
+              </Td>
+            </Tr>
+          ))}
+        </Tbody>
+      </Table>
+    </TableContainer>
+  );
+};
+
 const App = () => {
   return (
     <Flex justifyItems={"stretch"} direction={"column"} h={"100%"}>
@@ -287,6 +356,7 @@ const App = () => {
             <Flex direction={"column"}>
               <ConfigurationsList />
               <SessionsList />
+              <ConnectionSetsList />
             </Flex>
           </Box>
         </Flex>
@@ -6,13 +6,115 @@ export interface ImportConfigurationPayload {
 }
 
 
+export interface ImportFailure {
+    source: string;
+    error: string;
+}
+
+export interface ImportConfigResult {
+    configPaths: string[];
+    failed: ImportFailure[];
+}
+
 export type Modals = null | "exit_confirmation";
 
 
-export type MainAction = "exit_confirmation";
+export type MainAction =
+    | "exit_confirmation"
+    | "directory_synced"
+    | "config_changed"
+    | "failover"
+    | "connection_set"
+    | "connection_set_disconnected";
 export type FromMainAction = {
     type: MainAction,
     data: unknown;
 }
 
-export type ExitConfirmationPayload = boolean;
\ No newline at end of file
+export type ExitConfirmationPayload = boolean;
+
+export interface ListingError {
+    path: string;
+    error: string;
+}
+
+// Objects whose properties could not be read are reported in errors
+// instead of failing the whole list
+export interface Listing<T> {
+    items: T[];
+    errors: ListingError[];
+}
+
+export interface DirectorySyncedPayload {
+    directory: string;
+    imported: string[];
+    reimported: string[];
+    removed: string[];
+    failed: string[];
+    summary: string;
+}
+
+export type SetFailurePolicy = "continue" | "stop" | "rollback";
+
+export interface ConnectionSet {
+    name: string;
+    configs: string[];
+    onFailure: SetFailurePolicy;
+    timeoutSecs: number;
+}
+
+export interface SetMember {
+    configPath: string;
+    sessionPath: string | null;
+    outcome?: "connected" | "failed" | "auth_failed" | "needs_input" | "timed_out";
+    message?: string;
+    alreadyConnected: boolean;
+}
+
+export interface SetReport {
+    set: string;
+    members: SetMember[];
+    rolledBack: string[];
+    summary: string;
+}
+
+export interface ProtocolFallback {
+    timeoutSecs: number;
+    tryPort443: boolean;
+    keepWorkingFallback: boolean;
+}
+
+export interface ConnectAttempt {
+    proto: string | null;
+    port: number | null;
+    sessionPath: string;
+    outcome: "connected" | "failed" | "auth_failed" | "needs_input" | "timed_out";
+    message?: string;
+}
+
+export interface ConnectReport {
+    sessionPath: string | null;
+    attempts: ConnectAttempt[];
+    fallback: string | null;
+}
+
+export interface LocalUser {
+    uid: number;
+    name: string | null;
+}
+
+export interface ConfigAccess {
+    configPath: string;
+    owner: LocalUser | null;
+    users: LocalUser[];
+    publicAccess: boolean;
+    lockedDown: boolean;
+    sealed: boolean;
+}
+
+export interface FailoverEventPayload {
+    group: string;
+    from: string | null;
+    to: string | null;
+    message: string;
+}
\ No newline at end of file
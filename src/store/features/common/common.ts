@@ -1,12 +1,18 @@
 import { createSlice, createAction } from "@reduxjs/toolkit";
 import { RootState } from "../../store";
-import { ImportConfigurationPayload, Modals } from "../../../common/types";
+import {
+  ConnectionSet,
+  ImportConfigurationPayload,
+  Modals,
+} from "../../../common/types";
 
 type CommonState = {
   configurationPathToImport: string;
   notificationsEnabled: boolean;
   currentModal: Modals;
   hasActiveSession: boolean;
+  importError: string | null;
+  connectionSets: ConnectionSet[];
 };
 
 const initialState: CommonState = {
@@ -14,6 +20,8 @@ const initialState: CommonState = {
   notificationsEnabled: false,
   currentModal: null,
   hasActiveSession: false,
+  importError: null,
+  connectionSets: [],
 };
 
 export const commonSlice = createSlice({
@@ -32,6 +40,12 @@ export const commonSlice = createSlice({
     setHasActiveSession: (state, action) => {
       state.hasActiveSession = action.payload;
     },
+    setImportError: (state, action) => {
+      state.importError = action.payload;
+    },
+    setConnectionSets: (state, action) => {
+      state.connectionSets = action.payload;
+    },
   },
 });
 
@@ -40,6 +54,8 @@ export const {
   setNotificationsEnabled,
   setCurrentModal,
   setHasActiveSession,
+  setImportError,
+  setConnectionSets,
 } = commonSlice.actions;
 
 export const invokeSelectFile = createAction("common/invokeSelectFile");
@@ -55,6 +71,12 @@ export const invokeDisconnectSession = createAction<string>(
 export const invokeConnectSession = createAction<string>(
   "common/invokeConnectSession"
 );
+export const invokeConnectConnectionSet = createAction<string>(
+  "common/invokeConnectConnectionSet"
+);
+export const invokeDisconnectConnectionSet = createAction<string>(
+  "common/invokeDisconnectConnectionSet"
+);
 
 export const invokeConfirmExit = createAction("common/invokeConfirmExit");
 export const invokeMinimizeToTray = createAction("common/invokeMinimizeToTray");
@@ -67,4 +89,9 @@ export const getCurrentModal = (state: RootState) => state.common.currentModal;
 export const getHasActiveSession = (state: RootState) =>
   state.common.hasActiveSession;
 
+export const getImportError = (state: RootState) => state.common.importError;
+
+export const getConnectionSets = (state: RootState) =>
+  state.common.connectionSets;
+
 export default commonSlice.reducer;
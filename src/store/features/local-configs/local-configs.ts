@@ -12,6 +12,20 @@ export type Config = {
   path: string;
   name: string;
   used_count: number;
+  // Missing when the installed openvpn3 does not expose the property
+  import_timestamp?: number;
+  last_used_timestamp?: number;
+  persistent?: boolean;
+  single_use?: boolean;
+  locked_down?: boolean;
+  readonly?: boolean;
+  valid?: boolean;
+  owner?: number;
+  dco?: boolean;
+  transfer_owner_session?: boolean;
+  public_access?: boolean;
+  acl?: number[];
+  tags?: string[];
 };
 
 export type Session = {
@@ -20,6 +34,7 @@ export type Session = {
   minor_code: number;
   status_message: string;
   session_created: number;
+  config_path?: string;
 };
 
 type LocalConfigsState = {
@@ -57,12 +72,24 @@ export const localConfigsSlice = createSlice({
     updateSessionStatus: (state, action: PayloadAction<LastSessionStatusPayload>) => {
       state.sessionsStatus[action.payload.path] = action.payload;
     },
+    updateConfig: (state, action: PayloadAction<Config>) => {
+      const index = state.configs.findIndex(
+        (config) => config.path === action.payload.path
+      );
+      if (index !== -1) {
+        state.configs[index] = action.payload;
+      }
+    },
   },
 });
 
 // Action creators are generated for each case reducer function
-export const { initializeConfigs, initializeSessions, updateSessionStatus } =
-  localConfigsSlice.actions;
+export const {
+  initializeConfigs,
+  initializeSessions,
+  updateSessionStatus,
+  updateConfig,
+} = localConfigsSlice.actions;
 
 export const getAllConfigs = (state: { localConfigs: LocalConfigsState }) =>
   state.localConfigs.configs;
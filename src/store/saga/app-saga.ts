@@ -20,14 +20,31 @@ import {
   invokeConfirmExit,
   setHasActiveSession,
   invokeMinimizeToTray,
+  setImportError,
+  setConnectionSets,
+  invokeConnectConnectionSet,
+  invokeDisconnectConnectionSet,
 } from "../features/common/common";
-import { ExitConfirmationPayload, FromMainAction, ImportConfigurationPayload } from "../../common/types";
+import {
+  ConnectReport,
+  ConnectionSet,
+  DirectorySyncedPayload,
+  ExitConfirmationPayload,
+  FailoverEventPayload,
+  FromMainAction,
+  ImportConfigResult,
+  ImportConfigurationPayload,
+  Listing,
+  ProtocolFallback,
+  SetReport,
+} from "../../common/types";
 import {
   Config,
   LastSessionStatusPayload,
   Session,
   initializeConfigs,
   initializeSessions,
+  updateConfig,
   updateSessionStatus,
 } from "../features/local-configs/local-configs";
 import { Log, addLog } from "../features/logs/logs";
@@ -71,12 +88,26 @@ function* processLog(log: Log) {
 }
 
 function* init() {
-  const configs: Config[] = yield call(invoke, "get_openvpn3_configs");
-  yield put(initializeConfigs(configs));
+  const configs: Listing<Config> = yield call(invoke, "get_openvpn3_configs");
+  yield put(initializeConfigs(configs.items));
 
-  const sessions = (yield call(invoke, "get_openvpn3_sessions")) as Session[];
+  const sessionsListing: Listing<Session> = yield call(
+    invoke,
+    "get_openvpn3_sessions"
+  );
+  const sessions = sessionsListing.items;
   yield put(initializeSessions(sessions));
 
+  const connectionSets: ConnectionSet[] = yield call(
+    invoke,
+    "get_connection_sets"
+  );
+  yield put(setConnectionSets(connectionSets));
+
+  for (const { path, error } of [...configs.errors, ...sessionsListing.errors]) {
+    console.warn(`Failed to read ${path}: ${error}`);
+  }
+
   for (const session of sessions) {
     const firstLog: Log = {
       path: session.path,
@@ -104,6 +135,41 @@ function* registerEvents() {
       data: event.payload
     });
   });
+
+  yield call(listen<DirectorySyncedPayload>, "directory_synced", (event) => {
+    fromMainChannel.put({
+      type: "directory_synced",
+      data: event.payload,
+    });
+  });
+
+  yield call(listen<Config>, "config_changed", (event) => {
+    fromMainChannel.put({
+      type: "config_changed",
+      data: event.payload,
+    });
+  });
+
+  yield call(listen<FailoverEventPayload>, "failover", (event) => {
+    fromMainChannel.put({
+      type: "failover",
+      data: event.payload,
+    });
+  });
+
+  yield call(listen<SetReport>, "connection_set", (event) => {
+    fromMainChannel.put({
+      type: "connection_set",
+      data: event.payload,
+    });
+  });
+
+  yield call(listen<string>, "connection_set_disconnected", (event) => {
+    fromMainChannel.put({
+      type: "connection_set_disconnected",
+      data: event.payload,
+    });
+  });
 }
 
 function* handleInvokeSelectFile() {
@@ -120,8 +186,37 @@ function* handleInvokeImportConfiguration(
     payload: action.payload,
   };
 
-  yield call(invoke, "import_openvpn3_config", invokeArgs);
-  yield put(setConfigurationPathToImport(""));
+  // Read and validation failures are reported as a rejected promise
+  try {
+    const result: ImportConfigResult = yield call(
+      invoke,
+      "import_openvpn3_config",
+      invokeArgs
+    );
+    const imported = result.configPaths.length;
+
+    // Profiles of a bundle that openvpn3 did not accept
+    yield put(
+      setImportError(
+        result.failed.length > 0
+          ? result.failed
+              .map(({ source, error }) => `${source}: ${error}`)
+              .join("\n")
+          : null
+      )
+    );
+    yield put(setConfigurationPathToImport(""));
+    sendNotification({
+      title: "Konewka",
+      body:
+        imported === 1
+          ? "Imported 1 configuration"
+          : `Imported ${imported} configurations`,
+    });
+  } catch (error) {
+    yield put(setImportError(String(error)));
+  }
+
   yield init();
 }
 
@@ -145,7 +240,46 @@ function* handleInvokeNewTunnel(
     payload: action.payload,
   };
 
-  yield call(invoke, "new_tunnel", invokeArgs);
+  const fallback: ProtocolFallback | null = yield call(
+    invoke,
+    "get_config_protocol_fallback",
+    invokeArgs
+  );
+
+  // With a fallback policy the session is connected right away, so that
+  // the backend can retry over TCP
+  if (fallback === null) {
+    yield call(invoke, "new_tunnel", invokeArgs);
+  } else {
+    try {
+      const report: ConnectReport = yield call(
+        invoke,
+        "connect_config",
+        invokeArgs
+      );
+      const last = report.attempts[report.attempts.length - 1];
+      if (report.fallback !== null) {
+        sendNotification({
+          title: "Konewka",
+          body: `Connected over ${report.fallback}`,
+        });
+      } else if (
+        last &&
+        last.outcome !== "connected" &&
+        last.outcome !== "needs_input"
+      ) {
+        sendNotification({
+          title: "Konewka",
+          body: last.message ?? `Connection ${last.outcome.replace("_", " ")}`,
+        });
+      }
+    } catch (error) {
+      sendNotification({
+        title: "Konewka",
+        body: String(error),
+      });
+    }
+  }
   yield init();
 }
 
@@ -190,7 +324,96 @@ function* watchActions() {
       yield put(setHasActiveSession(action.data));
       yield put(setCurrentModal("exit_confirmation"));
     }
+
+    if (action.type === "directory_synced") {
+      const report = action.data as DirectorySyncedPayload;
+      sendNotification({
+        title: "Konewka",
+        body: report.summary,
+      });
+      yield init();
+    }
+
+    if (action.type === "config_changed") {
+      yield put(updateConfig(action.data as Config));
+    }
+
+    if (action.type === "failover") {
+      const event = action.data as FailoverEventPayload;
+      sendNotification({
+        title: "Konewka",
+        body: event.message,
+      });
+      yield init();
+    }
+
+    if (action.type === "connection_set") {
+      const report = action.data as SetReport;
+      sendNotification({
+        title: "Konewka",
+        body: report.summary,
+      });
+      yield init();
+    }
+
+    if (action.type === "connection_set_disconnected") {
+      sendNotification({
+        title: "Konewka",
+        body: `${action.data as string}: disconnected`,
+      });
+      yield init();
+    }
+  }
+}
+
+function* handleInvokeConnectConnectionSet(
+  action: ReturnType<typeof invokeConnectConnectionSet>
+) {
+  type ConnectConnectionSetRecord = Record<string, string>;
+  const invokeArgs: ConnectConnectionSetRecord = {
+    payload: action.payload,
+  };
+
+  try {
+    const report: SetReport = yield call(
+      invoke,
+      "connect_connection_set",
+      invokeArgs
+    );
+    sendNotification({
+      title: "Konewka",
+      body: report.summary,
+    });
+  } catch (error) {
+    sendNotification({
+      title: "Konewka",
+      body: String(error),
+    });
   }
+  yield init();
+}
+
+function* handleInvokeDisconnectConnectionSet(
+  action: ReturnType<typeof invokeDisconnectConnectionSet>
+) {
+  type DisconnectConnectionSetRecord = Record<string, string>;
+  const invokeArgs: DisconnectConnectionSetRecord = {
+    payload: action.payload,
+  };
+
+  try {
+    yield call(invoke, "disconnect_connection_set", invokeArgs);
+    sendNotification({
+      title: "Konewka",
+      body: `${action.payload}: disconnected`,
+    });
+  } catch (error) {
+    sendNotification({
+      title: "Konewka",
+      body: String(error),
+    });
+  }
+  yield init();
 }
 
 function* handleInvokeConfirmExit() {
@@ -220,6 +443,14 @@ function* appSaga() {
   yield takeLatest(invokeNewTunnel.type, handleInvokeNewTunnel);
   yield takeLatest(invokeDisconnectSession.type, handleInvokeDisconnectSession);
   yield takeLatest(invokeConnectSession.type, handleInvokeConnectSession);
+  yield takeLatest(
+    invokeConnectConnectionSet.type,
+    handleInvokeConnectConnectionSet
+  );
+  yield takeLatest(
+    invokeDisconnectConnectionSet.type,
+    handleInvokeDisconnectConnectionSet
+  );
   yield takeLatest(invokeConfirmExit.type, handleInvokeConfirmExit);
   yield takeLatest(invokeMinimizeToTray.type, handleInvokeMinimizeToTray);
 }